@@ -0,0 +1,326 @@
+use crate::errors::GameError;
+use crate::players::{DisplayName, Player};
+use crate::registry::{PlayerRegistry, RegisteredPlayer};
+use crate::storage::{storage_error, write_atomically};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// Checked against when the username is unknown, so a failed login takes as
+/// long whether or not the account exists. Same Argon2 parameters as real
+/// hashes; no password matches it in practice.
+const DUMMY_PASSWORD_HASH: &str =
+    "$argon2id$v=19$m=19456,t=2,p=1$dEg4gTaWK53PHJGrory2sw$6KnTJUhVYUBcGmmVXEfdXx5595M1v4rkx1NdSXI0CQ8";
+
+/// Login details of a registered player. Guests are registry players without
+/// an account.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Account {
+    pub username: String,
+    /// Argon2id hash in PHC format, salt included.
+    password_hash: String,
+    pub player_id: u32,
+}
+
+/// Usernames are case-insensitive: 3 to 32 of `a-z`, `0-9`, `_`, `-` and `.`.
+fn normalize_username(username: &str) -> Result<String, GameError> {
+    let username = username.trim().to_ascii_lowercase();
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.';
+    if username.len() < 3 || username.len() > 32 || !username.chars().all(allowed) {
+        return Err(GameError::InvalidUsername);
+    }
+    Ok(username)
+}
+
+/// A password long enough to accept, already hashed. Made by
+/// [`hash_password`], which is slow on purpose, so callers can do the hashing
+/// before they borrow the store.
+#[derive(Debug, Clone)]
+pub struct HashedPassword(String);
+
+pub fn hash_password(password: &str) -> Result<HashedPassword, GameError> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(GameError::WeakPassword);
+    }
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(storage_error)?;
+    Ok(HashedPassword(hash.to_string()))
+}
+
+/// The stored hash a login is checked against, taken out of the store so the
+/// slow check can run without it.
+#[derive(Debug, Clone)]
+pub struct LoginAttempt {
+    player_id: Option<u32>,
+    password_hash: String,
+}
+
+impl LoginAttempt {
+    /// The player id behind the account. Unknown users and wrong passwords
+    /// fail the same way and take as long: unknown users are checked against
+    /// a dummy hash.
+    pub fn verify(&self, password: &str) -> Result<u32, GameError> {
+        let hash = PasswordHash::new(&self.password_hash).map_err(storage_error)?;
+        let verified = Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .is_ok();
+        match self.player_id {
+            Some(player_id) if verified => Ok(player_id),
+            _ => Err(GameError::InvalidCredentials),
+        }
+    }
+}
+
+/// Accounts by username, optionally saved to a JSON file after each change.
+#[derive(Debug)]
+pub struct AccountStore {
+    path: Option<PathBuf>,
+    accounts: BTreeMap<String, Account>,
+}
+
+impl AccountStore {
+    pub fn in_memory() -> Self {
+        AccountStore {
+            path: None,
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<Self, GameError> {
+        let path = path.as_ref().to_path_buf();
+        let mut store = AccountStore::in_memory();
+        if path.exists() {
+            let data = fs::read_to_string(&path).map_err(storage_error)?;
+            let accounts: Vec<Account> = serde_json::from_str(&data).map_err(storage_error)?;
+            store.accounts = accounts
+                .into_iter()
+                .map(|a| (a.username.clone(), a))
+                .collect();
+        }
+        store.path = Some(path);
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<(), GameError> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let accounts: Vec<&Account> = self.accounts.values().collect();
+        let data = serde_json::to_string_pretty(&accounts).map_err(storage_error)?;
+        write_atomically(path, &data)
+    }
+
+    pub fn get(&self, username: &str) -> Option<&Account> {
+        self.accounts.get(&username.trim().to_ascii_lowercase())
+    }
+
+    pub fn has_account(&self, player_id: u32) -> bool {
+        self.accounts.values().any(|a| a.player_id == player_id)
+    }
+
+    /// The normalized username, if it is valid and free.
+    pub fn check_username(&self, username: &str) -> Result<String, GameError> {
+        let username = normalize_username(username)?;
+        if self.accounts.contains_key(&username) {
+            return Err(GameError::UsernameTaken);
+        }
+        Ok(username)
+    }
+
+    /// Whether `guest_id` can get an account called `username`.
+    pub fn check_upgrade(
+        &self,
+        registry: &PlayerRegistry,
+        guest_id: u32,
+        username: &str,
+    ) -> Result<String, GameError> {
+        if registry.get(guest_id).is_none() {
+            return Err(GameError::UnknownPlayer(guest_id));
+        }
+        if self.has_account(guest_id) {
+            return Err(GameError::AccountExists);
+        }
+        self.check_username(username)
+    }
+
+    /// Create a new player in `registry` with an account attached.
+    pub fn sign_up(
+        &mut self,
+        registry: &mut PlayerRegistry,
+        username: &str,
+        password: HashedPassword,
+        name: DisplayName,
+    ) -> Result<u32, GameError> {
+        let username = self.check_username(username)?;
+        let player_id = *registry.register(name)?.get_id();
+        self.insert(username, password, player_id)?;
+        Ok(player_id)
+    }
+
+    /// Attach an account to an existing guest, keeping their id and history.
+    pub fn upgrade_guest(
+        &mut self,
+        registry: &PlayerRegistry,
+        guest_id: u32,
+        username: &str,
+        password: HashedPassword,
+    ) -> Result<(), GameError> {
+        let username = self.check_upgrade(registry, guest_id, username)?;
+        self.insert(username, password, guest_id)
+    }
+
+    fn insert(
+        &mut self,
+        username: String,
+        password: HashedPassword,
+        player_id: u32,
+    ) -> Result<(), GameError> {
+        self.accounts.insert(
+            username.clone(),
+            Account {
+                username,
+                password_hash: password.0,
+                player_id,
+            },
+        );
+        self.save()
+    }
+
+    /// What logging in as `username` is checked against. Unknown users get
+    /// the dummy hash.
+    pub fn login_attempt(&self, username: &str) -> LoginAttempt {
+        match self.get(username) {
+            Some(account) => LoginAttempt {
+                player_id: Some(account.player_id),
+                password_hash: account.password_hash.clone(),
+            },
+            None => LoginAttempt {
+                player_id: None,
+                password_hash: String::from(DUMMY_PASSWORD_HASH),
+            },
+        }
+    }
+
+    pub fn login(&self, username: &str, password: &str) -> Result<u32, GameError> {
+        self.login_attempt(username).verify(password)
+    }
+
+    /// The registered player an account belongs to.
+    pub fn player<'a>(
+        &self,
+        registry: &'a PlayerRegistry,
+        username: &str,
+    ) -> Option<&'a RegisteredPlayer> {
+        self.get(username).and_then(|a| registry.get(a.player_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accounts_sign_up_and_login() -> Result<(), GameError> {
+        let mut registry = PlayerRegistry::in_memory();
+        let mut accounts = AccountStore::in_memory();
+        let id = accounts.sign_up(
+            &mut registry,
+            "Ada_L",
+            hash_password("correct horse")?,
+            DisplayName::new("Ada")?,
+        )?;
+        assert_eq!(accounts.login("ada_l", "correct horse"), Ok(id));
+        assert_eq!(
+            accounts.login("ada_l", "wrong horse"),
+            Err(GameError::InvalidCredentials)
+        );
+        assert_eq!(
+            accounts.login("nobody", "correct horse"),
+            Err(GameError::InvalidCredentials)
+        );
+        assert_eq!(
+            accounts
+                .player(&registry, "ADA_L")
+                .map(|p| String::from(p.get_name())),
+            Some(String::from("Ada"))
+        );
+        // the password is never stored as is, and salts differ.
+        let first = accounts.get("ada_l").unwrap().password_hash.clone();
+        assert!(!first.contains("correct horse"));
+        accounts.sign_up(
+            &mut registry,
+            "bob",
+            hash_password("correct horse")?,
+            DisplayName::new("Bob")?,
+        )?;
+        assert_ne!(first, accounts.get("bob").unwrap().password_hash);
+        Ok(())
+    }
+
+    #[test]
+    fn accounts_validation() -> Result<(), GameError> {
+        let mut registry = PlayerRegistry::in_memory();
+        let mut accounts = AccountStore::in_memory();
+        let name = DisplayName::new("Ada")?;
+        let password = hash_password("correct horse")?;
+        assert_eq!(
+            accounts.sign_up(&mut registry, "a", password.clone(), name.clone()),
+            Err(GameError::InvalidUsername)
+        );
+        assert_eq!(
+            hash_password("short").map(|_| ()),
+            Err(GameError::WeakPassword)
+        );
+        accounts.sign_up(&mut registry, "ada", password.clone(), name.clone())?;
+        assert_eq!(
+            accounts.check_username("ADA"),
+            Err(GameError::UsernameTaken)
+        );
+        assert_eq!(
+            accounts.sign_up(&mut registry, "ADA", password, name),
+            Err(GameError::UsernameTaken)
+        );
+        // failed sign-ups don't leave players behind.
+        assert_eq!(registry.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn accounts_upgrade_guest() -> Result<(), GameError> {
+        let mut registry = PlayerRegistry::in_memory();
+        let mut accounts = AccountStore::in_memory();
+        let guest = *registry.register(DisplayName::new("Guest")?)?.get_id();
+        let password = hash_password("correct horse")?;
+        accounts.upgrade_guest(&registry, guest, "guest", password.clone())?;
+        assert_eq!(accounts.login("guest", "correct horse"), Ok(guest));
+        assert_eq!(
+            accounts.upgrade_guest(&registry, guest, "other", password.clone()),
+            Err(GameError::AccountExists)
+        );
+        assert_eq!(
+            accounts.upgrade_guest(&registry, 42, "ghost", password),
+            Err(GameError::UnknownPlayer(42))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn accounts_dummy_hash_costs_like_a_real_one() -> Result<(), GameError> {
+        let real = hash_password("correct horse")?;
+        let real = PasswordHash::new(&real.0).map_err(storage_error)?;
+        let dummy = PasswordHash::new(DUMMY_PASSWORD_HASH).map_err(storage_error)?;
+        assert_eq!(dummy.algorithm, real.algorithm);
+        assert_eq!(dummy.version, real.version);
+        assert_eq!(dummy.params, real.params);
+        Ok(())
+    }
+}
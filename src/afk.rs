@@ -0,0 +1,93 @@
+use crate::game::Team;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// The spymaster role of `team`, offered to `offered_to` because the
+/// spymaster went quiet.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpymasterOffer {
+    pub team: Team,
+    pub idle_spymaster: u32,
+    pub offered_to: u32,
+}
+
+/// When each player last did something, and the running spymaster offer.
+/// Times are the game clock's. Not part of snapshots.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Activity {
+    last_active: BTreeMap<u32, Duration>,
+    pub(crate) offer: Option<SpymasterOffer>,
+    /// Players who turned the role down this turn.
+    pub(crate) declined: Vec<u32>,
+}
+
+impl Activity {
+    pub(crate) fn record(&mut self, player_id: u32, now: Duration) {
+        self.last_active.insert(player_id, now);
+    }
+
+    pub(crate) fn last_active(&self, player_id: u32) -> Option<Duration> {
+        self.last_active.get(&player_id).cloned()
+    }
+
+    /// Latest action of any of `players`.
+    pub(crate) fn last_active_of<'a, I>(&self, players: I) -> Option<Duration>
+    where
+        I: IntoIterator<Item = &'a u32>,
+    {
+        players
+            .into_iter()
+            .filter_map(|id| self.last_active(*id))
+            .max()
+    }
+
+    /// Who gets the role next: the most recently active of `candidates` that
+    /// hasn't declined, lowest id first among equals.
+    pub(crate) fn pick_successor(&self, candidates: &[u32]) -> Option<u32> {
+        candidates
+            .iter()
+            .filter(|id| !self.declined.contains(id))
+            .max_by(|a, b| {
+                self.last_active(**a)
+                    .cmp(&self.last_active(**b))
+                    .then(b.cmp(a))
+            })
+            .cloned()
+    }
+
+    /// Forget the offer and who declined it, e.g. when the turn changes.
+    pub(crate) fn reset_offer(&mut self) {
+        self.offer = None;
+        self.declined.clear();
+    }
+
+    /// Start over on a new clock.
+    pub(crate) fn clear(&mut self) {
+        self.last_active.clear();
+        self.reset_offer();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn afk_successor_is_most_recently_active() {
+        let mut activity = Activity::default();
+        assert_eq!(activity.pick_successor(&[3, 5]), Some(3));
+        activity.record(5, Duration::from_secs(10));
+        activity.record(7, Duration::from_secs(4));
+        assert_eq!(activity.pick_successor(&[3, 5, 7]), Some(5));
+        activity.declined.push(5);
+        assert_eq!(activity.pick_successor(&[3, 5, 7]), Some(7));
+        assert_eq!(
+            activity.last_active_of(&[3, 7]),
+            Some(Duration::from_secs(4))
+        );
+        activity.reset_offer();
+        assert_eq!(activity.pick_successor(&[3, 5, 7]), Some(5));
+        assert_eq!(activity.pick_successor(&[]), None);
+    }
+}
@@ -1,47 +1,54 @@
-use crate::errors::{InvalidError, InvalidMoveError};
+use crate::errors::GameError;
 
 use rand::prelude::*;
 use rand::thread_rng;
+use serde::{Deserialize, Serialize};
 
-fn bitmap_for_pos(pos_list: &[usize]) -> Result<u32, InvalidError> {
+pub(crate) fn bitmap_for_pos(pos_list: &[usize]) -> Result<u32, GameError> {
     let mut bm: u32 = 0;
     for pos in pos_list.iter() {
         if *pos > 31 {
-            return Err(InvalidError::new(
-                "bitmap_for_pos only takes member values 0 <= x < 32",
-            ));
+            return Err(GameError::PositionOutOfRange(*pos));
         }
-        bm = bm | (1 << pos);
+        bm |= 1 << pos;
     }
-    return Ok(bm);
+    Ok(bm)
 }
 
-fn pos_from_bitmap(bitmap: &u32) -> Vec<usize> {
+pub(crate) fn pos_from_bitmap(bitmap: &u32) -> Vec<usize> {
     let mut res: Vec<usize> = Vec::new();
     for i in 0..32 {
         if bitmap & (1 << i) != 0 {
             res.push(i);
         }
     }
-    return res;
+    res
 }
 
-fn num_ones(num: &u32) -> u32 {
+pub(crate) fn num_ones(num: &u32) -> u32 {
     let mut x: u32 = *num;
     let mut s = 0;
     for _ in 0..32 {
         s += x & 1;
-        x = x >> 1;
+        x >>= 1;
     }
-    return s;
+    s
 }
 
-fn is_bit_set(num: &u32, idx: usize) -> bool {
+pub(crate) fn is_bit_set(num: &u32, idx: usize) -> bool {
     let m = 1 << idx;
     num & m == m
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TileKind {
+    Grey,
+    TeamOne,
+    TeamTwo,
+    Danger,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Board {
     words: Vec<String>,
     danger_index: u8,
@@ -52,17 +59,34 @@ pub struct Board {
 }
 
 impl Board {
-    pub fn new(vocab: &Vec<String>) -> Result<Self, InvalidError> {
+    pub fn new(vocab: &[String]) -> Result<Self, GameError> {
+        // 1/3 partitions.
+        let num_team = (vocab.len() / 3) as u8;
+        Board::with_card_counts(vocab, num_team, num_team)
+    }
+
+    /// Deal a board with the given number of cards per team. The rest are grey,
+    /// one of which is the danger tile.
+    pub fn with_card_counts(
+        vocab: &[String],
+        num_team_one: u8,
+        num_team_two: u8,
+    ) -> Result<Self, GameError> {
         if vocab.len() != 25 {
-            return Err(InvalidError::new("Vocab must be 25 words"));
+            return Err(GameError::WrongVocabSize {
+                expected: 25,
+                found: vocab.len(),
+            });
+        }
+        let num_team_one = num_team_one as usize;
+        let num_team_two = num_team_two as usize;
+        if num_team_one + num_team_two >= vocab.len() {
+            return Err(GameError::CardsDontFit);
         }
         // shuffle words
         let mut rng = thread_rng();
         let mut indices: Vec<usize> = (0..vocab.len()).collect();
         indices.shuffle(&mut rng);
-        // 1/3 partitions.
-        let num_team_one = (vocab.len() / 3) as usize;
-        let num_team_two = num_team_one;
         let num_grey: usize = vocab.len() - num_team_two - num_team_one;
         // get data.
         let grey = &indices[0..num_grey];
@@ -70,11 +94,11 @@ impl Board {
         let team_two = &indices[(num_grey + num_team_one)..];
         //
         let board = Board {
-            words: vocab.iter().map(|x| String::from(x)).collect(),
+            words: vocab.iter().map(String::from).collect(),
             danger_index: grey[0] as u8,
             grey_indices: bitmap_for_pos(&grey[1..])?,
-            team_one_indices: bitmap_for_pos(&team_one[..])?,
-            team_two_indices: bitmap_for_pos(&team_two[..])?,
+            team_one_indices: bitmap_for_pos(team_one)?,
+            team_two_indices: bitmap_for_pos(team_two)?,
             unraveled_indices: 0,
         };
         Ok(board)
@@ -85,15 +109,15 @@ impl Board {
     }
 
     pub fn get_grey_indices_list(&self) -> Vec<usize> {
-        return pos_from_bitmap(&self.grey_indices);
+        pos_from_bitmap(&self.grey_indices)
     }
 
     pub fn get_team_one_indices_list(&self) -> Vec<usize> {
-        return pos_from_bitmap(&self.team_one_indices);
+        pos_from_bitmap(&self.team_one_indices)
     }
 
     pub fn get_team_two_indices_list(&self) -> Vec<usize> {
-        return pos_from_bitmap(&self.team_two_indices);
+        pos_from_bitmap(&self.team_two_indices)
     }
 
     pub fn danger_index(&self) -> u8 {
@@ -104,18 +128,58 @@ impl Board {
         is_bit_set(&self.unraveled_indices, idx)
     }
 
-    pub fn unravel_word(&mut self, idx: usize) -> Result<(), InvalidMoveError> {
+    pub fn unravel_word(&mut self, idx: usize) -> Result<(), GameError> {
+        if idx >= self.words.len() {
+            return Err(GameError::TileOutOfRange(idx));
+        }
         if self.is_word_unravelled(idx) {
-            return Err(InvalidMoveError::new(
-                "Trying to unveil already known word.",
-            ));
+            return Err(GameError::TileAlreadyRevealed(idx));
+        }
+        self.unraveled_indices |= 1 << idx;
+        Ok(())
+    }
+
+    pub fn veil_word(&mut self, idx: usize) -> Result<(), GameError> {
+        if idx >= self.words.len() {
+            return Err(GameError::TileOutOfRange(idx));
+        }
+        if !self.is_word_unravelled(idx) {
+            return Err(GameError::TileNotRevealed(idx));
+        }
+        self.unraveled_indices &= !(1 << idx);
+        Ok(())
+    }
+
+    /// Check a board that didn't come from `with_card_counts`, e.g. one read
+    /// from a snapshot: 25 words, every tile of exactly one kind and nothing
+    /// marked outside the board.
+    pub fn validate(&self) -> Result<(), GameError> {
+        if self.words.len() != 25 || self.danger_index as usize >= self.words.len() {
+            return Err(GameError::SnapshotInvalidBoard);
+        }
+        let all_tiles: u32 = (1 << self.words.len()) - 1;
+        let danger: u32 = 1 << self.danger_index;
+        let kinds = [
+            danger,
+            self.grey_indices,
+            self.team_one_indices,
+            self.team_two_indices,
+        ];
+        let mut seen: u32 = 0;
+        for kind in kinds.iter() {
+            if seen & kind != 0 {
+                return Err(GameError::SnapshotInvalidBoard);
+            }
+            seen |= kind;
+        }
+        if seen != all_tiles || self.unraveled_indices & !all_tiles != 0 {
+            return Err(GameError::SnapshotInvalidBoard);
         }
-        self.unraveled_indices = self.unraveled_indices | (1 << idx);
         Ok(())
     }
 
     pub fn has_hidden_tiles(&self) -> bool {
-        !(((1 << self.danger_index) | self.unraveled_indices) == 0x01FFFFFF)
+        ((1 << self.danger_index) | self.unraveled_indices) != 0x01FFFFFF
     }
 
     pub fn get_team_one_pending_size(&self) -> u32 {
@@ -137,6 +201,18 @@ impl Board {
     pub fn is_grey_index(&self, idx: usize) -> bool {
         is_bit_set(&self.grey_indices, idx)
     }
+
+    pub fn tile_kind(&self, idx: usize) -> TileKind {
+        if idx == self.danger_index as usize {
+            TileKind::Danger
+        } else if self.is_team_one_index(idx) {
+            TileKind::TeamOne
+        } else if self.is_team_two_index(idx) {
+            TileKind::TeamTwo
+        } else {
+            TileKind::Grey
+        }
+    }
 }
 
 #[cfg(test)]
@@ -153,6 +229,39 @@ mod tests {
         assert_eq!(num_ones(&board.team_two_indices), 8);
     }
 
+    #[test]
+    fn test_board_with_card_counts() {
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        let board = Board::with_card_counts(&words, 9, 8).unwrap();
+        assert_eq!(board.get_team_one_pending_size(), 9);
+        assert_eq!(board.get_team_two_pending_size(), 8);
+        assert_eq!(num_ones(&board.grey_indices), 7);
+        assert!(!board.is_grey_index(board.danger_index() as usize));
+        // the danger tile needs a spot too.
+        assert!(Board::with_card_counts(&words, 12, 13).is_err());
+        assert!(Board::with_card_counts(&words, 12, 12).is_ok());
+    }
+
+    #[test]
+    fn test_board_validate() {
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        let board = Board::new(&words).unwrap();
+        assert!(board.validate().is_ok());
+        let mut bad = board.clone();
+        bad.danger_index = 25;
+        assert_eq!(bad.validate(), Err(GameError::SnapshotInvalidBoard));
+        let mut bad = board.clone();
+        bad.grey_indices |= bad.team_one_indices;
+        assert_eq!(bad.validate(), Err(GameError::SnapshotInvalidBoard));
+        let mut bad = board.clone();
+        bad.team_two_indices = 0;
+        assert_eq!(bad.validate(), Err(GameError::SnapshotInvalidBoard));
+        let mut bad = board;
+        bad.unraveled_indices = 1 << 30;
+        assert_eq!(bad.validate(), Err(GameError::SnapshotInvalidBoard));
+        assert_eq!(bad.veil_word(30), Err(GameError::TileOutOfRange(30)));
+    }
+
     #[test]
     fn test_board_vocab_matches_indices() {
         let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
@@ -179,8 +288,8 @@ mod tests {
         }
 
         // make sure all inds are used.
-        for i in 0..seen.len() {
-            assert!(seen[i], "{} index was not used anywhere", i);
+        for (i, used) in seen.iter().enumerate() {
+            assert!(*used, "{} index was not used anywhere", i);
         }
 
         // make sure vocab ordering is intact.
@@ -213,6 +322,20 @@ mod tests {
         }
     }
 
+    #[test]
+    fn board_veiling() {
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        let mut board = Board::new(&words).unwrap();
+        // can't hide a word that was never unravelled.
+        assert!(board.veil_word(3).is_err());
+        assert!(board.unravel_word(3).is_ok());
+        assert!(board.unravel_word(4).is_ok());
+        assert!(board.veil_word(3).is_ok());
+        assert!(!board.is_word_unravelled(3));
+        assert!(board.is_word_unravelled(4));
+        assert!(board.veil_word(3).is_err());
+    }
+
     #[test]
     fn test_board_has_hidden_tiles() {
         let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
@@ -227,6 +350,25 @@ mod tests {
         assert!(!board.has_hidden_tiles());
     }
 
+    #[test]
+    fn test_board_tile_kind() {
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        let board = Board::new(&words).unwrap();
+        for i in 0..words.len() {
+            let expected = if i == board.danger_index() as usize {
+                TileKind::Danger
+            } else if board.is_team_one_index(i) {
+                TileKind::TeamOne
+            } else if board.is_team_two_index(i) {
+                TileKind::TeamTwo
+            } else {
+                assert!(board.is_grey_index(i));
+                TileKind::Grey
+            };
+            assert_eq!(board.tile_kind(i), expected, "wrong kind at: {}", i);
+        }
+    }
+
     #[test]
     fn test_bitmap_for_pos() {
         let test_cases: Vec<(Vec<usize>, u32)> = vec![
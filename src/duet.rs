@@ -0,0 +1,513 @@
+use crate::board::{bitmap_for_pos, is_bit_set, num_ones, pos_from_bitmap};
+use crate::errors::GameError;
+use crate::game::{GameState, GameStatus, InProgressGame, InitialGame};
+use crate::players::Player;
+use rand::prelude::*;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_TURN_TOKENS: u8 = 9;
+/// Distinct agents across both keys.
+pub const DUET_AGENTS: u32 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Side {
+    A,
+    B,
+}
+
+impl Side {
+    pub fn other(&self) -> Side {
+        match self {
+            Side::A => Side::B,
+            Side::B => Side::A,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DuetTile {
+    Agent,
+    Bystander,
+    Assassin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DuetWinReason {
+    AllAgentsFound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DuetLossReason {
+    AssassinRevealed,
+    OutOfTokens,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DuetMoveResult {
+    Continue,
+    TurnOver,
+    Win(DuetWinReason),
+    Loss(DuetLossReason),
+}
+
+/// One side's key card.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DuetKey {
+    agent_indices: u32,
+    assassin_indices: u32,
+}
+
+impl DuetKey {
+    fn tile(&self, idx: usize) -> DuetTile {
+        if is_bit_set(&self.agent_indices, idx) {
+            DuetTile::Agent
+        } else if is_bit_set(&self.assassin_indices, idx) {
+            DuetTile::Assassin
+        } else {
+            DuetTile::Bystander
+        }
+    }
+}
+
+/// Words plus the two key cards. Each side sees 9 agents and 3 assassins, laid
+/// out so that 15 distinct agents have to be found together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuetBoard {
+    words: Vec<String>,
+    side_a_key: DuetKey,
+    side_b_key: DuetKey,
+    found_agents: u32,
+    // bystanders revealed from the clue giver's key, per clue giver.
+    side_a_bystanders: u32,
+    side_b_bystanders: u32,
+}
+
+impl DuetBoard {
+    pub fn new(vocab: &[String]) -> Result<Self, GameError> {
+        if vocab.len() != 25 {
+            return Err(GameError::WrongVocabSize {
+                expected: 25,
+                found: vocab.len(),
+            });
+        }
+        let mut rng = thread_rng();
+        let mut indices: Vec<usize> = (0..vocab.len()).collect();
+        indices.shuffle(&mut rng);
+        // (side a, side b) layout of the standard duet key card.
+        let both_agents = &indices[0..3];
+        let a_agent_b_bystander = &indices[3..8];
+        let a_agent_b_assassin = &indices[8..9];
+        let a_bystander_b_agent = &indices[9..14];
+        let a_assassin_b_agent = &indices[14..15];
+        let both_assassins = &indices[15..16];
+        let a_assassin_b_bystander = &indices[16..17];
+        let a_bystander_b_assassin = &indices[17..18];
+
+        let side_a_agents = [both_agents, a_agent_b_bystander, a_agent_b_assassin].concat();
+        let side_a_assassins =
+            [a_assassin_b_agent, both_assassins, a_assassin_b_bystander].concat();
+        let side_b_agents = [both_agents, a_bystander_b_agent, a_assassin_b_agent].concat();
+        let side_b_assassins =
+            [a_agent_b_assassin, both_assassins, a_bystander_b_assassin].concat();
+        Ok(DuetBoard {
+            words: vocab.iter().map(String::from).collect(),
+            side_a_key: DuetKey {
+                agent_indices: bitmap_for_pos(&side_a_agents)?,
+                assassin_indices: bitmap_for_pos(&side_a_assassins)?,
+            },
+            side_b_key: DuetKey {
+                agent_indices: bitmap_for_pos(&side_b_agents)?,
+                assassin_indices: bitmap_for_pos(&side_b_assassins)?,
+            },
+            found_agents: 0,
+            side_a_bystanders: 0,
+            side_b_bystanders: 0,
+        })
+    }
+
+    pub fn words(&self) -> &Vec<String> {
+        &self.words
+    }
+
+    fn key(&self, side: Side) -> &DuetKey {
+        match side {
+            Side::A => &self.side_a_key,
+            Side::B => &self.side_b_key,
+        }
+    }
+
+    /// What `side`'s key card says about a tile.
+    pub fn tile(&self, side: Side, idx: usize) -> DuetTile {
+        self.key(side).tile(idx)
+    }
+
+    pub fn get_agent_indices_list(&self, side: Side) -> Vec<usize> {
+        pos_from_bitmap(&self.key(side).agent_indices)
+    }
+
+    pub fn is_agent_found(&self, idx: usize) -> bool {
+        is_bit_set(&self.found_agents, idx)
+    }
+
+    pub fn num_found_agents(&self) -> u32 {
+        num_ones(&self.found_agents)
+    }
+
+    /// Agents on `side`'s key that are still hidden.
+    pub fn pending_agents(&self, side: Side) -> u32 {
+        let agents = self.key(side).agent_indices;
+        num_ones(&(agents ^ self.found_agents & agents))
+    }
+
+    fn bystanders(&mut self, side: Side) -> &mut u32 {
+        match side {
+            Side::A => &mut self.side_a_bystanders,
+            Side::B => &mut self.side_b_bystanders,
+        }
+    }
+
+    /// Reveal a tile using the clue giver's key.
+    pub fn unravel_word(&mut self, clue_giver: Side, idx: usize) -> Result<DuetTile, GameError> {
+        if idx >= self.words.len() {
+            return Err(GameError::TileOutOfRange(idx));
+        }
+        if self.is_agent_found(idx) {
+            return Err(GameError::TileAlreadyRevealed(idx));
+        }
+        if is_bit_set(self.bystanders(clue_giver), idx) {
+            return Err(GameError::TileAlreadyRevealed(idx));
+        }
+        let tile = self.tile(clue_giver, idx);
+        match tile {
+            DuetTile::Agent => self.found_agents |= 1 << idx,
+            DuetTile::Bystander => *self.bystanders(clue_giver) |= 1 << idx,
+            DuetTile::Assassin => {}
+        }
+        Ok(tile)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuetClue {
+    pub side: Side,
+    pub word: String,
+    pub number: u8,
+}
+
+/// Cooperative two-sided game. Sides take turns giving clues from their own
+/// key card while the other side guesses, sharing one pool of turn tokens.
+#[derive(Debug)]
+pub struct DuetGame<S, P: Player> {
+    board: DuetBoard,
+    side_a_players: BTreeMap<u32, P>,
+    side_b_players: BTreeMap<u32, P>,
+    tokens_left: u8,
+    clue_giver: Side,
+    current_clue: Option<DuetClue>,
+    result: Option<DuetMoveResult>,
+    state: S,
+}
+
+impl<S, P: Player> DuetGame<S, P> {
+    pub fn get_board(&self) -> &DuetBoard {
+        &self.board
+    }
+
+    pub fn get_tokens_left(&self) -> u8 {
+        self.tokens_left
+    }
+
+    pub fn get_clue_giver(&self) -> Side {
+        self.clue_giver
+    }
+
+    pub fn get_current_clue(&self) -> Option<&DuetClue> {
+        self.current_clue.as_ref()
+    }
+
+    /// `Win` or `Loss` once the game is over.
+    pub fn get_result(&self) -> Option<DuetMoveResult> {
+        self.result
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn get_player_side(&self, player: &P) -> Option<Side> {
+        if self.side_a_players.contains_key(player.get_id()) {
+            Some(Side::A)
+        } else if self.side_b_players.contains_key(player.get_id()) {
+            Some(Side::B)
+        } else {
+            None
+        }
+    }
+}
+
+impl<S: GameState, P: Player> DuetGame<S, P> {
+    pub fn status(&self) -> GameStatus {
+        if self.is_over() {
+            GameStatus::Over
+        } else if self.state.has_started() {
+            GameStatus::InProgress
+        } else {
+            GameStatus::Waiting
+        }
+    }
+}
+
+impl<P: Player> DuetGame<InitialGame, P> {
+    pub fn new(vocab: &[String], tokens: u8) -> Result<Self, GameError> {
+        if tokens == 0 {
+            return Err(GameError::NotEnoughTokens);
+        }
+        Ok(DuetGame {
+            board: DuetBoard::new(vocab)?,
+            side_a_players: BTreeMap::new(),
+            side_b_players: BTreeMap::new(),
+            tokens_left: tokens,
+            clue_giver: Side::A,
+            current_clue: None,
+            result: None,
+            state: InitialGame {},
+        })
+    }
+
+    pub fn add_player_side_a(&mut self, player: P) {
+        self.side_b_players.remove(player.get_id());
+        self.side_a_players.insert(*player.get_id(), player);
+    }
+
+    pub fn add_player_side_b(&mut self, player: P) {
+        self.side_a_players.remove(player.get_id());
+        self.side_b_players.insert(*player.get_id(), player);
+    }
+
+    pub fn can_begin(&self) -> bool {
+        !self.side_a_players.is_empty() && !self.side_b_players.is_empty()
+    }
+
+    pub fn begin(self) -> Result<DuetGame<InProgressGame, P>, GameError> {
+        if !self.can_begin() {
+            return Err(GameError::NotEnoughPlayers { per_team: 1 });
+        }
+        Ok(DuetGame {
+            board: self.board,
+            side_a_players: self.side_a_players,
+            side_b_players: self.side_b_players,
+            tokens_left: self.tokens_left,
+            clue_giver: self.clue_giver,
+            current_clue: self.current_clue,
+            result: self.result,
+            state: InProgressGame {},
+        })
+    }
+}
+
+impl<P: Player> DuetGame<InProgressGame, P> {
+    fn check_running(&self) -> Result<(), GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        Ok(())
+    }
+
+    pub fn give_clue(&mut self, player: &P, word: &str, number: u8) -> Result<(), GameError> {
+        self.check_running()?;
+        if self.get_player_side(player) != Some(self.clue_giver) {
+            return Err(GameError::NotYourTurn);
+        }
+        if self.current_clue.is_some() {
+            return Err(GameError::ClueAlreadyGiven);
+        }
+        let word = word.trim();
+        if word.is_empty() {
+            return Err(GameError::EmptyClue);
+        }
+        self.current_clue = Some(DuetClue {
+            side: self.clue_giver,
+            word: String::from(word),
+            number,
+        });
+        Ok(())
+    }
+
+    pub fn try_unravel(&mut self, player: &P, tile_id: u8) -> Result<DuetMoveResult, GameError> {
+        self.check_running()?;
+        if self.get_player_side(player) != Some(self.clue_giver.other()) {
+            return Err(GameError::NotYourTurn);
+        }
+        if self.current_clue.is_none() {
+            return Err(GameError::WaitingForClue);
+        }
+        let result = match self.board.unravel_word(self.clue_giver, tile_id as usize)? {
+            DuetTile::Agent => {
+                if self.board.num_found_agents() == DUET_AGENTS {
+                    DuetMoveResult::Win(DuetWinReason::AllAgentsFound)
+                } else {
+                    DuetMoveResult::Continue
+                }
+            }
+            DuetTile::Bystander => self.finish_turn(),
+            DuetTile::Assassin => DuetMoveResult::Loss(DuetLossReason::AssassinRevealed),
+        };
+        if let DuetMoveResult::Win(_) | DuetMoveResult::Loss(_) = result {
+            self.result = Some(result);
+        }
+        Ok(result)
+    }
+
+    /// Guessers stop for this turn. Costs a token like a bystander does.
+    pub fn end_turn(&mut self, player: &P) -> Result<DuetMoveResult, GameError> {
+        self.check_running()?;
+        if self.get_player_side(player) != Some(self.clue_giver.other()) {
+            return Err(GameError::NotYourTurn);
+        }
+        if self.current_clue.is_none() {
+            return Err(GameError::WaitingForClue);
+        }
+        let result = self.finish_turn();
+        if let DuetMoveResult::Loss(_) = result {
+            self.result = Some(result);
+        }
+        Ok(result)
+    }
+
+    fn finish_turn(&mut self) -> DuetMoveResult {
+        self.current_clue = None;
+        self.tokens_left -= 1;
+        if self.tokens_left == 0 {
+            return DuetMoveResult::Loss(DuetLossReason::OutOfTokens);
+        }
+        // a side whose agents are all found has nothing left to clue.
+        if self.board.pending_agents(self.clue_giver.other()) > 0 {
+            self.clue_giver = self.clue_giver.other();
+        }
+        DuetMoveResult::TurnOver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_support::words;
+    use crate::players::SimplePlayer;
+
+    fn setup_game(tokens: u8) -> Result<DuetGame<InProgressGame, SimplePlayer>, GameError> {
+        let mut game = DuetGame::new(&words(), tokens)?;
+        assert!(!game.can_begin());
+        game.add_player_side_a(SimplePlayer::new("a", 1));
+        game.add_player_side_b(SimplePlayer::new("b", 2));
+        assert_eq!(game.status(), GameStatus::Waiting);
+        let game = game.begin()?;
+        assert_eq!(game.status(), GameStatus::InProgress);
+        Ok(game)
+    }
+
+    #[test]
+    fn duet_board_key_layout() -> Result<(), GameError> {
+        let board = DuetBoard::new(&words())?;
+        let mut agents = 0u32;
+        for side in [Side::A, Side::B].iter() {
+            let count = |kind: DuetTile| (0..25).filter(|i| board.tile(*side, *i) == kind).count();
+            assert_eq!(count(DuetTile::Agent), 9);
+            assert_eq!(count(DuetTile::Assassin), 3);
+            assert_eq!(count(DuetTile::Bystander), 13);
+            for idx in board.get_agent_indices_list(*side) {
+                agents |= 1 << idx;
+            }
+        }
+        assert_eq!(num_ones(&agents), DUET_AGENTS);
+        Ok(())
+    }
+
+    #[test]
+    fn duet_turn_flow() -> Result<(), GameError> {
+        let mut game = setup_game(DEFAULT_TURN_TOKENS)?;
+        let a = SimplePlayer::new("a", 1);
+        let b = SimplePlayer::new("b", 2);
+        let board = game.get_board().clone();
+        let agent = board.get_agent_indices_list(Side::A)[0] as u8;
+        let bystander = (0..25)
+            .find(|i| board.tile(Side::A, *i) == DuetTile::Bystander)
+            .unwrap() as u8;
+
+        // side b can't guess before side a gives a clue.
+        assert!(game.try_unravel(&b, agent).is_err());
+        assert!(game.give_clue(&b, "clue", 1).is_err());
+        game.give_clue(&a, "clue", 2)?;
+        // clue givers don't guess.
+        assert!(game.try_unravel(&a, agent).is_err());
+        assert_eq!(game.try_unravel(&b, agent)?, DuetMoveResult::Continue);
+        assert!(game.try_unravel(&b, agent).is_err());
+        assert_eq!(game.try_unravel(&b, bystander)?, DuetMoveResult::TurnOver);
+        assert_eq!(game.get_tokens_left(), DEFAULT_TURN_TOKENS - 1);
+        assert_eq!(game.get_clue_giver(), Side::B);
+        assert!(game.get_current_clue().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn duet_assassin_loses() -> Result<(), GameError> {
+        let mut game = setup_game(DEFAULT_TURN_TOKENS)?;
+        let a = SimplePlayer::new("a", 1);
+        let b = SimplePlayer::new("b", 2);
+        let assassin = (0..25)
+            .find(|i| game.get_board().tile(Side::A, *i) == DuetTile::Assassin)
+            .unwrap() as u8;
+        game.give_clue(&a, "clue", 1)?;
+        assert_eq!(
+            game.try_unravel(&b, assassin)?,
+            DuetMoveResult::Loss(DuetLossReason::AssassinRevealed)
+        );
+        assert_eq!(game.status(), GameStatus::Over);
+        assert!(game.give_clue(&a, "clue", 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn duet_out_of_tokens() -> Result<(), GameError> {
+        let mut game = setup_game(2)?;
+        let a = SimplePlayer::new("a", 1);
+        let b = SimplePlayer::new("b", 2);
+        game.give_clue(&a, "clue", 1)?;
+        assert_eq!(game.end_turn(&b)?, DuetMoveResult::TurnOver);
+        game.give_clue(&b, "clue", 1)?;
+        assert_eq!(
+            game.end_turn(&a)?,
+            DuetMoveResult::Loss(DuetLossReason::OutOfTokens)
+        );
+        assert_eq!(
+            game.get_result(),
+            Some(DuetMoveResult::Loss(DuetLossReason::OutOfTokens))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn duet_find_all_agents() -> Result<(), GameError> {
+        let mut game = setup_game(DEFAULT_TURN_TOKENS)?;
+        let a = SimplePlayer::new("a", 1);
+        let b = SimplePlayer::new("b", 2);
+        let board = game.get_board().clone();
+        game.give_clue(&a, "clue", 9)?;
+        for idx in board.get_agent_indices_list(Side::A) {
+            assert_eq!(game.try_unravel(&b, idx as u8)?, DuetMoveResult::Continue);
+        }
+        game.end_turn(&b)?;
+        // agents already found from side a's key don't need finding again.
+        game.give_clue(&b, "clue", 6)?;
+        let mut res = DuetMoveResult::Continue;
+        for idx in board.get_agent_indices_list(Side::B) {
+            if !game.get_board().is_agent_found(idx) {
+                res = game.try_unravel(&a, idx as u8)?;
+            }
+        }
+        assert_eq!(res, DuetMoveResult::Win(DuetWinReason::AllAgentsFound));
+        assert!(game.is_over());
+        Ok(())
+    }
+}
@@ -1,22 +1,350 @@
+use crate::i18n::{Locale, Localized};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
 use std::fmt;
+use std::sync::Arc;
 
+/// The underlying error some `GameError`s wrap. Shared so `GameError` stays
+/// `Clone`, and compared by message.
 #[derive(Debug, Clone)]
-pub struct InvalidError {
-    msg: String,
+pub struct ErrorSource(Arc<dyn Error + Send + Sync>);
+
+impl ErrorSource {
+    pub fn new<E: Error + Send + Sync + 'static>(error: E) -> Self {
+        ErrorSource(Arc::new(error))
+    }
 }
 
-impl InvalidError {
-    pub fn new(msg: &str) -> Self {
-        return InvalidError {
-            msg: String::from(msg),
-        };
+impl PartialEq for ErrorSource {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_string() == other.0.to_string()
+    }
+}
+
+impl fmt::Display for ErrorSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Everything that can go wrong setting up or playing a game.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameError {
+    // setup
+    WrongVocabSize { expected: usize, found: usize },
+    CardsDontFit,
+    TeamTooSmall,
+    TeamWithoutCards,
+    HandicapTooLarge,
+    NotEnoughTokens,
+    PositionOutOfRange(usize),
+    BestOfNeedsOddRounds,
+    FirstToNeedsWins,
+    SeriesDecided,
+
+    // roster
+    AlreadyOnTeam,
+    SpymasterIndexOutOfRange { index: usize, players: usize },
+    HostNotInGame,
+    NotEnoughPlayers { per_team: usize },
+    InvalidDisplayName,
+    InvalidAvatar,
+
+    // moves
+    GameOver,
+    GameNotOver,
+    GameNotStarted,
+    GameAlreadyStarted,
+    SpectatorCannotAct,
+    NotInGame,
+    NotYourTurn,
+    NotSpymaster,
+    SpymasterCannotGuess,
+    NotHost,
+    WaitingForClue,
+    ClueAlreadyGiven,
+    EmptyClue,
+    ClueOnBoard,
+    TileOutOfRange(usize),
+    TileAlreadyRevealed(usize),
+    TileNotRevealed(usize),
+    NothingToUndo,
+    NoSpymasterOffer,
+
+    // sessions
+    LobbyNotFound,
+    InvalidSession,
+    SessionLobbyMismatch,
+
+    // accounts
+    InvalidUsername,
+    WeakPassword,
+    UsernameTaken,
+    AccountExists,
+    InvalidCredentials,
+
+    // matchmaking
+    InvalidMatchSize(usize),
+    AlreadyQueued,
+    NotQueued,
+
+    // player registry
+    UnknownPlayer(u32),
+    UnsupportedRegistryVersion(u32),
+    Storage(ErrorSource),
+
+    // snapshots
+    UnsupportedSnapshotVersion(u32),
+    SnapshotStateMismatch,
+    SnapshotSpymasterMissing,
+    SnapshotInvalidBoard,
+    SnapshotScoreMismatch,
+    SnapshotDuplicatePlayer(u32),
+    SnapshotInvalidHistory,
+    SnapshotInvalidTurn,
+    SnapshotHostMissing,
+    Serialization(ErrorSource),
+}
+
+impl GameError {
+    /// Stable, machine-readable name of the error, e.g. `not_your_turn`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            GameError::WrongVocabSize { .. } => "wrong_vocab_size",
+            GameError::CardsDontFit => "cards_dont_fit",
+            GameError::TeamTooSmall => "team_too_small",
+            GameError::TeamWithoutCards => "team_without_cards",
+            GameError::HandicapTooLarge => "handicap_too_large",
+            GameError::NotEnoughTokens => "not_enough_tokens",
+            GameError::PositionOutOfRange(_) => "position_out_of_range",
+            GameError::BestOfNeedsOddRounds => "best_of_needs_odd_rounds",
+            GameError::FirstToNeedsWins => "first_to_needs_wins",
+            GameError::SeriesDecided => "series_decided",
+            GameError::AlreadyOnTeam => "already_on_team",
+            GameError::SpymasterIndexOutOfRange { .. } => "spymaster_index_out_of_range",
+            GameError::HostNotInGame => "host_not_in_game",
+            GameError::NotEnoughPlayers { .. } => "not_enough_players",
+            GameError::InvalidDisplayName => "invalid_display_name",
+            GameError::InvalidAvatar => "invalid_avatar",
+            GameError::GameOver => "game_over",
+            GameError::GameNotOver => "game_not_over",
+            GameError::GameNotStarted => "game_not_started",
+            GameError::GameAlreadyStarted => "game_already_started",
+            GameError::SpectatorCannotAct => "spectator_cannot_act",
+            GameError::NotInGame => "not_in_game",
+            GameError::NotYourTurn => "not_your_turn",
+            GameError::NotSpymaster => "not_spymaster",
+            GameError::SpymasterCannotGuess => "spymaster_cannot_guess",
+            GameError::NotHost => "not_host",
+            GameError::WaitingForClue => "waiting_for_clue",
+            GameError::ClueAlreadyGiven => "clue_already_given",
+            GameError::EmptyClue => "empty_clue",
+            GameError::ClueOnBoard => "clue_on_board",
+            GameError::TileOutOfRange(_) => "tile_out_of_range",
+            GameError::TileAlreadyRevealed(_) => "tile_already_revealed",
+            GameError::TileNotRevealed(_) => "tile_not_revealed",
+            GameError::NothingToUndo => "nothing_to_undo",
+            GameError::NoSpymasterOffer => "no_spymaster_offer",
+            GameError::LobbyNotFound => "lobby_not_found",
+            GameError::InvalidSession => "invalid_session",
+            GameError::SessionLobbyMismatch => "session_lobby_mismatch",
+            GameError::InvalidUsername => "invalid_username",
+            GameError::WeakPassword => "weak_password",
+            GameError::UsernameTaken => "username_taken",
+            GameError::AccountExists => "account_exists",
+            GameError::InvalidCredentials => "invalid_credentials",
+            GameError::InvalidMatchSize(_) => "invalid_match_size",
+            GameError::AlreadyQueued => "already_queued",
+            GameError::NotQueued => "not_queued",
+            GameError::UnknownPlayer(_) => "unknown_player",
+            GameError::UnsupportedRegistryVersion(_) => "unsupported_registry_version",
+            GameError::Storage(_) => "storage",
+            GameError::UnsupportedSnapshotVersion(_) => "unsupported_snapshot_version",
+            GameError::SnapshotStateMismatch => "snapshot_state_mismatch",
+            GameError::SnapshotSpymasterMissing => "snapshot_spymaster_missing",
+            GameError::SnapshotInvalidBoard => "snapshot_invalid_board",
+            GameError::SnapshotScoreMismatch => "snapshot_score_mismatch",
+            GameError::SnapshotDuplicatePlayer(_) => "snapshot_duplicate_player",
+            GameError::SnapshotInvalidHistory => "snapshot_invalid_history",
+            GameError::SnapshotInvalidTurn => "snapshot_invalid_turn",
+            GameError::SnapshotHostMissing => "snapshot_host_missing",
+            GameError::Serialization(_) => "serialization",
+        }
     }
 }
 
-impl fmt::Display for InvalidError {
+impl fmt::Display for GameError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "invalid value: {}", self.msg)
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+impl Error for GameError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            GameError::Serialization(source) | GameError::Storage(source) => {
+                Some(source.0.as_ref())
+            }
+            _ => None,
+        }
     }
 }
 
-pub type InvalidMoveError = InvalidError;
+/// RFC 7807 problem details, the JSON body web handlers send for a `GameError`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: String,
+    pub code: String,
+}
+
+impl GameError {
+    /// 400 for bad input, 401 for a missing or revoked session token
+    /// or a failed login, 403 for acting without the right role, 404 for
+    /// lobbies, players and queue entries that don't exist, 409 for moves the
+    /// game state doesn't allow right now and 500 when saved data can't be
+    /// read or written.
+    pub fn status_code(&self) -> u16 {
+        match self {
+            GameError::WrongVocabSize { .. }
+            | GameError::CardsDontFit
+            | GameError::TeamTooSmall
+            | GameError::TeamWithoutCards
+            | GameError::HandicapTooLarge
+            | GameError::NotEnoughTokens
+            | GameError::PositionOutOfRange(_)
+            | GameError::BestOfNeedsOddRounds
+            | GameError::FirstToNeedsWins
+            | GameError::SpymasterIndexOutOfRange { .. }
+            | GameError::HostNotInGame
+            | GameError::InvalidDisplayName
+            | GameError::InvalidAvatar
+            | GameError::InvalidUsername
+            | GameError::WeakPassword
+            | GameError::InvalidMatchSize(_)
+            | GameError::EmptyClue
+            | GameError::ClueOnBoard
+            | GameError::TileOutOfRange(_)
+            | GameError::UnsupportedSnapshotVersion(_)
+            | GameError::SnapshotSpymasterMissing
+            | GameError::SnapshotInvalidBoard
+            | GameError::SnapshotScoreMismatch
+            | GameError::SnapshotDuplicatePlayer(_)
+            | GameError::SnapshotInvalidHistory
+            | GameError::SnapshotInvalidTurn
+            | GameError::SnapshotHostMissing
+            | GameError::Serialization(_) => 400,
+            GameError::InvalidSession | GameError::InvalidCredentials => 401,
+            GameError::SpectatorCannotAct
+            | GameError::NotSpymaster
+            | GameError::SpymasterCannotGuess
+            | GameError::NotHost
+            | GameError::SessionLobbyMismatch => 403,
+            GameError::NotInGame
+            | GameError::LobbyNotFound
+            | GameError::UnknownPlayer(_)
+            | GameError::NotQueued => 404,
+            GameError::UnsupportedRegistryVersion(_) | GameError::Storage(_) => 500,
+            GameError::SeriesDecided
+            | GameError::UsernameTaken
+            | GameError::AccountExists
+            | GameError::AlreadyQueued
+            | GameError::AlreadyOnTeam
+            | GameError::NotEnoughPlayers { .. }
+            | GameError::GameOver
+            | GameError::GameNotOver
+            | GameError::GameNotStarted
+            | GameError::GameAlreadyStarted
+            | GameError::NotYourTurn
+            | GameError::WaitingForClue
+            | GameError::ClueAlreadyGiven
+            | GameError::TileAlreadyRevealed(_)
+            | GameError::TileNotRevealed(_)
+            | GameError::NothingToUndo
+            | GameError::NoSpymasterOffer
+            | GameError::SnapshotStateMismatch => 409,
+        }
+    }
+
+    pub fn problem(&self) -> Problem {
+        self.problem_in(Locale::En)
+    }
+
+    /// Same as `problem`, with `detail` in the given language.
+    pub fn problem_in(&self, locale: Locale) -> Problem {
+        let status = self.status_code();
+        Problem {
+            kind: format!("/problems/{}", self.code()),
+            title: String::from(reason_phrase(status)),
+            status,
+            detail: self.localize(locale),
+            code: String::from(self.code()),
+        }
+    }
+}
+
+/// The standard reason phrase for the status codes `status_code` returns.
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        500 => "Internal Server Error",
+        _ => "Error",
+    }
+}
+
+/// Older name for `GameError`.
+pub type InvalidError = GameError;
+/// Older name for `GameError`.
+pub type InvalidMoveError = GameError;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_codes_and_messages() {
+        let err = GameError::NotYourTurn;
+        assert_eq!(err.code(), "not_your_turn");
+        assert_eq!(err.to_string(), "Not your team's turn");
+        let err = GameError::WrongVocabSize {
+            expected: 25,
+            found: 3,
+        };
+        assert_eq!(err.code(), "wrong_vocab_size");
+        assert_eq!(err.to_string(), "Vocab must be 25 words, got 3");
+    }
+
+    #[test]
+    fn errors_status_and_problem() {
+        assert_eq!(GameError::EmptyClue.status_code(), 400);
+        assert_eq!(GameError::NotHost.status_code(), 403);
+        assert_eq!(GameError::NotInGame.status_code(), 404);
+        assert_eq!(GameError::NotYourTurn.status_code(), 409);
+        let problem = GameError::NotYourTurn.problem();
+        assert_eq!(problem.status, 409);
+        assert_eq!(problem.title, "Conflict");
+        assert_eq!(problem.code, "not_your_turn");
+        let json = serde_json::to_value(&problem).unwrap();
+        assert_eq!(json["type"], "/problems/not_your_turn");
+        assert_eq!(json["detail"], "Not your team's turn");
+    }
+
+    #[test]
+    fn errors_compose_with_std_error() {
+        fn parse() -> Result<(), Box<dyn Error>> {
+            let inner = serde_json::from_str::<u32>("nope").unwrap_err();
+            Err(GameError::Serialization(ErrorSource::new(inner)))?;
+            Ok(())
+        }
+        let err = parse().unwrap_err();
+        assert!(err.source().is_some());
+        assert!(GameError::GameOver.source().is_none());
+    }
+}
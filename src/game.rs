@@ -1,21 +1,33 @@
+use crate::afk::{Activity, SpymasterOffer};
 use crate::board::Board;
-use crate::errors::{InvalidError, InvalidMoveError};
-use crate::players::Player;
-use std::collections::HashMap;
+use crate::clock::{Clock, SystemClock};
+use crate::errors::GameError;
+use crate::hooks::{MoveContext, MoveOutcome, RuleHook};
+use crate::observer::{GameEvent, GameObserver, Observers, RosterChange};
+use crate::players::{Player, Presence};
+use crate::rules::{AssassinRule, GuessLimit, Handicap, RuleSet};
+use crate::snapshot::{GameSnapshot, SNAPSHOT_VERSION};
+use crate::stats::PlayerStats;
+use crate::views::{PlayerView, SpectatorView, SpymasterView};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryInto;
+use std::sync::Arc;
+use std::time::Duration;
 
 static TARGET_SCORE: u8 = 0;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum Team {
     TeamOne,
     TeamTwo,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum WinReason {
     ScoreReached,
     OpponentDangerDraw,
+    OpponentOutOfTime,
 }
 
 #[derive(Debug, PartialEq)]
@@ -24,64 +36,455 @@ pub enum MoveResult {
     Continue,
 }
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clue {
+    pub team: Team,
+    pub word: String,
+    pub number: u8,
+}
+
+/// Everything needed to take back a single unravel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnravelRecord {
+    tile_id: u8,
+    team_one_score: u8,
+    team_two_score: u8,
+    next_turn: Option<Team>,
+    current_clue: Option<Clue>,
+    guesses_left: Option<u8>,
+    player_id: Option<u32>,
+    /// How long the turn had run. Kept instead of its start so the record
+    /// survives a restore onto another clock.
+    turn_elapsed: Duration,
+    team_one_time_left: Option<Duration>,
+    team_two_time_left: Option<Duration>,
+}
+
+/// End-of-game record, kept in the round history across rematches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameResult {
+    pub winner: Team,
+    pub reason: WinReason,
+    pub starting_team: Team,
+    pub team_one_score: u8,
+    pub team_two_score: u8,
+    pub team_one_players: Vec<u32>,
+    pub team_two_players: Vec<u32>,
+    pub team_one_spymaster: Option<u32>,
+    pub team_two_spymaster: Option<u32>,
+    pub player_stats: BTreeMap<u32, PlayerStats>,
+    pub handicap: Option<Handicap>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RematchOptions {
+    /// Hand the spymaster role to the next player on each team.
+    pub rotate_spymasters: bool,
+    /// Let the team that lost the previous round go first.
+    pub losing_team_starts: bool,
+}
+
+/// Optional time limits. `turn_limit` passes the turn once it runs out,
+/// `team_clock` is a chess-style budget per team that loses the game at zero.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct TimerConfig {
+    pub turn_limit: Option<Duration>,
+    pub team_clock: Option<Duration>,
+    /// Offer the role to a teammate once a spymaster has owed a clue this long.
+    pub spymaster_idle: Option<Duration>,
+    /// Skip the turn of a team none of whose players did anything for this long.
+    pub team_idle: Option<Duration>,
+}
+
 #[derive(Debug)]
 pub struct Game<S, P: Player> {
     board: Board,
-    team_one_players: HashMap<u32, P>,
-    team_two_players: HashMap<u32, P>,
+    team_one_players: BTreeMap<u32, P>,
+    team_two_players: BTreeMap<u32, P>,
+    spectators: BTreeMap<u32, P>,
+    spectators_see_key_after_end: bool,
     team_one_spymaster_ind: Option<usize>,
     team_two_spymaster_ind: Option<usize>,
     team_one_score: u8,
     team_two_score: u8,
     next_turn: Option<Team>,
+    starting_team: Team,
+    rules: RuleSet,
+    current_clue: Option<Clue>,
+    guesses_left: Option<u8>,
+    host_id: Option<u32>,
+    winner: Option<(Team, WinReason)>,
+    unravel_history: Vec<UnravelRecord>,
+    round_history: Vec<GameResult>,
+    timers: TimerConfig,
+    clock: Arc<dyn Clock>,
+    turn_started_at: Duration,
+    /// How long the turn in progress had already run when the game was
+    /// restored. Counts towards the turn until it ends.
+    turn_carried: Duration,
+    team_one_time_left: Option<Duration>,
+    team_two_time_left: Option<Duration>,
+    player_stats: BTreeMap<u32, PlayerStats>,
+    /// Connection status reported by the server, over what the players say.
+    presence: BTreeMap<u32, Presence>,
+    activity: Activity,
+    hooks: Vec<Box<dyn RuleHook>>,
+    observers: Observers,
     state: S,
 }
 
+pub fn other_team(team: Team) -> Team {
+    match team {
+        Team::TeamOne => Team::TeamTwo,
+        Team::TeamTwo => Team::TeamOne,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GameStatus {
+    Waiting,
+    InProgress,
+    Over,
+}
+
+pub trait GameState {
+    fn has_started(&self) -> bool;
+}
+
 #[derive(Debug)]
 pub struct InitialGame {}
 
+impl GameState for InitialGame {
+    fn has_started(&self) -> bool {
+        false
+    }
+}
+
 #[derive(Debug)]
 pub struct InProgressGame {}
 
+impl GameState for InProgressGame {
+    fn has_started(&self) -> bool {
+        true
+    }
+}
+
 impl<S, P: Player> Game<S, P> {
     pub fn get_team_one_score(&self) -> u8 {
-        self.team_one_score.clone()
+        self.team_one_score
     }
     pub fn get_team_two_score(&self) -> u8 {
-        self.team_two_score.clone()
+        self.team_two_score
+    }
+
+    pub fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn get_next_turn(&self) -> Option<Team> {
+        self.next_turn
+    }
+
+    pub fn get_winner(&self) -> Option<(Team, WinReason)> {
+        self.winner
+    }
+
+    /// Players of team one, ordered by id. Spymaster indices refer to this order.
+    pub fn get_team_one_players(&self) -> Vec<&P> {
+        self.team_one_players.values().collect()
+    }
+
+    /// Players of team two, ordered by id. Spymaster indices refer to this order.
+    pub fn get_team_two_players(&self) -> Vec<&P> {
+        self.team_two_players.values().collect()
+    }
+
+    pub fn get_team_one_spymaster(&self) -> Option<&P> {
+        self.team_one_spymaster_ind
+            .and_then(|ind| self.team_one_players.values().nth(ind))
+    }
+
+    pub fn get_team_two_spymaster(&self) -> Option<&P> {
+        self.team_two_spymaster_ind
+            .and_then(|ind| self.team_two_players.values().nth(ind))
+    }
+
+    pub fn get_rules(&self) -> &RuleSet {
+        &self.rules
+    }
+
+    pub fn get_current_clue(&self) -> Option<&Clue> {
+        self.current_clue.as_ref()
+    }
+
+    /// Guesses left this turn. `None` when guessing is unlimited.
+    pub fn get_guesses_left(&self) -> Option<u8> {
+        self.guesses_left
     }
 
     pub fn has_enough_players(&self) -> bool {
-        !(self.team_one_players.len() < 2
-            || self.team_two_players.len() < 2
+        let min_size = self.rules.min_team_size;
+        !(self.team_one_players.len() < min_size
+            || self.team_two_players.len() < min_size
             || self.team_one_spymaster_ind.is_none()
-            || self.team_one_spymaster_ind.is_none())
+            || self.team_two_spymaster_ind.is_none())
+    }
+
+    /// Register an observer. It only sees events from now on.
+    pub fn add_observer(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.add(observer);
+    }
+
+    /// Add a house rule after the ones already registered. Snapshots don't
+    /// keep hooks, so restored games need theirs added again.
+    pub fn add_hook(&mut self, hook: Box<dyn RuleHook>) {
+        self.hooks.push(hook);
+    }
+
+    fn notify_roster(&mut self, player_id: u32, change: RosterChange) {
+        self.observers
+            .notify(GameEvent::RosterChanged { player_id, change });
     }
 
     pub fn add_player_team_one(&mut self, player: P) {
-        self.team_one_players.insert(*player.get_id(), player);
+        self.seat_player(Team::TeamOne, player);
     }
 
     pub fn add_player_team_two(&mut self, player: P) {
-        self.team_two_players.insert(*player.get_id(), player);
+        self.seat_player(Team::TeamTwo, player);
+    }
+
+    /// Put `player` on `team`, taking them off the spectators or the other
+    /// team. A spymaster who switches sides gives up the role.
+    fn seat_player(&mut self, team: Team, player: P) {
+        let player_id = *player.get_id();
+        if self.spectators.remove(&player_id).is_some() {
+            self.notify_roster(player_id, RosterChange::LeftSpectators);
+        }
+        // spymasters are kept by position, so remember who they are.
+        let team_one_spymaster = self.get_team_one_spymaster().map(|p| *p.get_id());
+        let team_two_spymaster = self.get_team_two_spymaster().map(|p| *p.get_id());
+        match team {
+            Team::TeamOne => {
+                self.team_two_players.remove(&player_id);
+                self.team_one_players.insert(player_id, player);
+            }
+            Team::TeamTwo => {
+                self.team_one_players.remove(&player_id);
+                self.team_two_players.insert(player_id, player);
+            }
+        }
+        self.team_one_spymaster_ind =
+            spymaster_ind_for(&self.team_one_players, team_one_spymaster).unwrap_or(None);
+        self.team_two_spymaster_ind =
+            spymaster_ind_for(&self.team_two_players, team_two_spymaster).unwrap_or(None);
+        self.notify_roster(player_id, RosterChange::Joined(team));
+    }
+
+    /// Let someone watch without playing. Players already on a team can't spectate.
+    pub fn add_spectator(&mut self, player: P) -> Result<(), GameError> {
+        if self.get_player_team(&player).is_some() {
+            return Err(GameError::AlreadyOnTeam);
+        }
+        let player_id = *player.get_id();
+        self.spectators.insert(player_id, player);
+        self.notify_roster(player_id, RosterChange::Spectating);
+        Ok(())
     }
 
-    pub fn set_team_one_spymaster(&mut self, ind: usize) -> Result<(), InvalidError> {
+    pub fn remove_spectator(&mut self, player: &P) -> Option<P> {
+        let removed = self.spectators.remove(player.get_id());
+        if removed.is_some() {
+            self.notify_roster(*player.get_id(), RosterChange::LeftSpectators);
+        }
+        removed
+    }
+
+    pub fn is_spectator(&self, player: &P) -> bool {
+        self.spectators.contains_key(player.get_id())
+    }
+
+    /// Spectators, ordered by id.
+    pub fn get_spectators(&self) -> Vec<&P> {
+        self.spectators.values().collect()
+    }
+
+    /// Whether spectators get the spymaster view once the game is over.
+    pub fn set_spectators_see_key_after_end(&mut self, allowed: bool) {
+        self.spectators_see_key_after_end = allowed;
+    }
+
+    pub fn set_team_one_spymaster(&mut self, ind: usize) -> Result<(), GameError> {
         if ind >= self.team_one_players.len() {
-            return Err(InvalidError::new("Spymaster index exceeds num players"));
+            return Err(GameError::SpymasterIndexOutOfRange {
+                index: ind,
+                players: self.team_one_players.len(),
+            });
         }
         self.team_one_spymaster_ind = Some(ind);
+        if let Some(player_id) = self.get_team_one_spymaster().map(|p| *p.get_id()) {
+            self.notify_roster(player_id, RosterChange::BecameSpymaster(Team::TeamOne));
+        }
         Ok(())
     }
 
-    pub fn set_team_two_spymaster(&mut self, ind: usize) -> Result<(), InvalidError> {
+    pub fn set_team_two_spymaster(&mut self, ind: usize) -> Result<(), GameError> {
         if ind >= self.team_two_players.len() {
-            return Err(InvalidError::new("Spymaster index exceeds num players"));
+            return Err(GameError::SpymasterIndexOutOfRange {
+                index: ind,
+                players: self.team_two_players.len(),
+            });
         }
         self.team_two_spymaster_ind = Some(ind);
+        if let Some(player_id) = self.get_team_two_spymaster().map(|p| *p.get_id()) {
+            self.notify_roster(player_id, RosterChange::BecameSpymaster(Team::TeamTwo));
+        }
+        Ok(())
+    }
+
+    pub fn get_host_id(&self) -> Option<&u32> {
+        self.host_id.as_ref()
+    }
+
+    /// Make `player` the lobby host. Only the host can approve an undo.
+    pub fn set_host(&mut self, player: &P) -> Result<(), GameError> {
+        if self.get_player_team(player).is_none() {
+            return Err(GameError::HostNotInGame);
+        }
+        self.host_id = Some(*player.get_id());
         Ok(())
     }
 
+    pub fn is_over(&self) -> bool {
+        self.winner.is_some()
+    }
+
+    pub fn get_starting_team(&self) -> Team {
+        self.starting_team
+    }
+
+    /// Results of the previous rounds played by this lobby, oldest first.
+    pub fn get_round_history(&self) -> &[GameResult] {
+        &self.round_history
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        let (winner, reason) = self.winner?;
+        Some(GameResult {
+            winner,
+            reason,
+            starting_team: self.starting_team,
+            team_one_score: self.team_one_score,
+            team_two_score: self.team_two_score,
+            team_one_players: self.team_one_players.keys().cloned().collect(),
+            team_two_players: self.team_two_players.keys().cloned().collect(),
+            team_one_spymaster: self.get_team_one_spymaster().map(|p| *p.get_id()),
+            team_two_spymaster: self.get_team_two_spymaster().map(|p| *p.get_id()),
+            player_stats: self.player_stats.clone(),
+            handicap: self.rules.handicap,
+        })
+    }
+
+    /// Counters for everyone who revealed a tile or gave a clue this game.
+    pub fn get_player_stats(&self) -> &BTreeMap<u32, PlayerStats> {
+        &self.player_stats
+    }
+
+    pub fn get_stats_for(&self, player: &P) -> PlayerStats {
+        self.player_stats
+            .get(player.get_id())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get_timers(&self) -> TimerConfig {
+        self.timers
+    }
+
+    /// Replace the time source, e.g. with a `ManualClock` in tests. The current
+    /// turn restarts at the new clock's `now`.
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.turn_started_at = clock.now();
+        self.activity.clear();
+        self.clock = clock;
+    }
+
+    /// Note that a player did something, so they don't count as idle. Game
+    /// actions are recorded automatically.
+    pub fn record_activity(&mut self, player: &P) {
+        self.activity.record(*player.get_id(), self.clock.now());
+    }
+
+    /// How long since the player last did something, `None` if they never did.
+    pub fn get_idle_time(&self, player: &P) -> Option<Duration> {
+        let last = self.activity.last_active(*player.get_id())?;
+        Some(self.clock.now().checked_sub(last).unwrap_or_default())
+    }
+
+    /// The spymaster role currently offered to a teammate of an idle spymaster.
+    pub fn get_spymaster_offer(&self) -> Option<&SpymasterOffer> {
+        self.activity.offer.as_ref()
+    }
+
+    fn turn_elapsed(&self) -> Duration {
+        self.clock
+            .now()
+            .checked_sub(self.turn_started_at)
+            .unwrap_or_default()
+            + self.turn_carried
+    }
+
+    /// Time left on a team's clock, counting the turn in progress.
+    pub fn get_team_time_left(&self, team: Team) -> Option<Duration> {
+        let left = match team {
+            Team::TeamOne => self.team_one_time_left?,
+            Team::TeamTwo => self.team_two_time_left?,
+        };
+        if self.next_turn == Some(team) && !self.is_over() {
+            return Some(left.checked_sub(self.turn_elapsed()).unwrap_or_default());
+        }
+        Some(left)
+    }
+
+    /// Time left before the current turn passes automatically.
+    pub fn get_turn_time_left(&self) -> Option<Duration> {
+        let limit = self.turn_limit_for(self.next_turn?)?;
+        Some(limit.checked_sub(self.turn_elapsed()).unwrap_or_default())
+    }
+
+    /// The turn limit, with any handicap time for `team` added.
+    fn turn_limit_for(&self, team: Team) -> Option<Duration> {
+        self.timers
+            .turn_limit
+            .map(|limit| limit + self.rules.extra_time(team))
+    }
+
+    /// The team clock a team starts a game with.
+    fn starting_clock_for(&self, team: Team) -> Option<Duration> {
+        self.timers
+            .team_clock
+            .map(|clock| clock + self.rules.extra_time(team))
+    }
+
+    /// Whether a player is connected. The server's word counts over the
+    /// player's own.
+    pub fn get_presence(&self, player: &P) -> Presence {
+        self.presence
+            .get(player.get_id())
+            .cloned()
+            .unwrap_or_else(|| player.get_presence())
+    }
+
+    /// Record a player's connection status, telling observers if it changed.
+    pub fn set_presence(&mut self, player_id: u32, presence: Presence) {
+        if self.presence.insert(player_id, presence) != Some(presence) {
+            self.observers.notify(GameEvent::PresenceChanged {
+                player_id,
+                presence,
+            });
+        }
+    }
+
     pub fn get_player_team(&self, player: &P) -> Option<Team> {
         if self.team_one_players.contains_key(player.get_id()) {
             Some(Team::TeamOne)
@@ -93,18 +496,266 @@ impl<S, P: Player> Game<S, P> {
     }
 }
 
+impl<S: GameState, P: Player> Game<S, P> {
+    pub fn status(&self) -> GameStatus {
+        if self.is_over() {
+            GameStatus::Over
+        } else if self.state.has_started() {
+            GameStatus::InProgress
+        } else {
+            GameStatus::Waiting
+        }
+    }
+
+    /// What a guesser gets to see. Hidden tiles carry no colour.
+    pub fn player_view(&self) -> PlayerView {
+        PlayerView::new(self)
+    }
+
+    /// What a spymaster gets to see, key card included.
+    pub fn spymaster_view(&self) -> SpymasterView {
+        SpymasterView::new(self)
+    }
+
+    /// Same as the player view, unless the game is over and spectators are
+    /// allowed to see the key card.
+    pub fn spectator_view(&self) -> SpectatorView {
+        if self.is_over() && self.spectators_see_key_after_end {
+            SpectatorView::Full(self.spymaster_view())
+        } else {
+            SpectatorView::Plain(self.player_view())
+        }
+    }
+}
+
+impl<S: GameState, P: Player + Clone> Game<S, P> {
+    pub fn snapshot(&self) -> GameSnapshot<P> {
+        GameSnapshot {
+            version: SNAPSHOT_VERSION,
+            started: self.state.has_started(),
+            board: self.board.clone(),
+            team_one_players: self.team_one_players.values().cloned().collect(),
+            team_two_players: self.team_two_players.values().cloned().collect(),
+            spectators: self.spectators.values().cloned().collect(),
+            spectators_see_key_after_end: self.spectators_see_key_after_end,
+            team_one_spymaster: self.get_team_one_spymaster().map(|p| *p.get_id()),
+            team_two_spymaster: self.get_team_two_spymaster().map(|p| *p.get_id()),
+            team_one_score: self.team_one_score,
+            team_two_score: self.team_two_score,
+            next_turn: self.next_turn,
+            starting_team: self.starting_team,
+            rules: self.rules,
+            current_clue: self.current_clue.clone(),
+            guesses_left: self.guesses_left,
+            host_id: self.host_id,
+            winner: self.winner,
+            unravel_history: self.unravel_history.clone(),
+            round_history: self.round_history.clone(),
+            timers: self.timers,
+            turn_elapsed: if self.state.has_started() && !self.is_over() {
+                self.turn_elapsed()
+            } else {
+                Duration::default()
+            },
+            team_one_time_left: self.get_team_time_left(Team::TeamOne),
+            team_two_time_left: self.get_team_time_left(Team::TeamTwo),
+            player_stats: self.player_stats.clone(),
+        }
+    }
+}
+
+fn spymaster_ind_for<P>(
+    players: &BTreeMap<u32, P>,
+    spymaster: Option<u32>,
+) -> Result<Option<usize>, GameError> {
+    match spymaster {
+        Some(id) => match players.keys().position(|x| *x == id) {
+            Some(ind) => Ok(Some(ind)),
+            None => Err(GameError::SnapshotSpymasterMissing),
+        },
+        None => Ok(None),
+    }
+}
+
+/// Snapshots come from outside, so nothing in them is taken on trust: the
+/// board, scores, rosters and unravel history must agree with each other.
+fn check_snapshot<P: Player>(snapshot: &GameSnapshot<P>) -> Result<(), GameError> {
+    let board = &snapshot.board;
+    board.validate()?;
+    if snapshot.team_one_score as u32 != board.get_team_one_pending_size()
+        || snapshot.team_two_score as u32 != board.get_team_two_pending_size()
+    {
+        return Err(GameError::SnapshotScoreMismatch);
+    }
+    // a running game always has a team to move, one not begun has none yet.
+    let running = snapshot.started && snapshot.winner.is_none();
+    if (running && snapshot.next_turn.is_none())
+        || (!snapshot.started && snapshot.next_turn.is_some())
+    {
+        return Err(GameError::SnapshotInvalidTurn);
+    }
+    let mut ids = BTreeSet::new();
+    let players = snapshot
+        .team_one_players
+        .iter()
+        .chain(snapshot.team_two_players.iter())
+        .chain(snapshot.spectators.iter());
+    for player in players {
+        if !ids.insert(*player.get_id()) {
+            return Err(GameError::SnapshotDuplicatePlayer(*player.get_id()));
+        }
+    }
+    if let Some(host_id) = snapshot.host_id {
+        let seated = snapshot
+            .team_one_players
+            .iter()
+            .chain(snapshot.team_two_players.iter())
+            .any(|p| *p.get_id() == host_id);
+        if !seated {
+            return Err(GameError::SnapshotHostMissing);
+        }
+    }
+    let mut tiles = BTreeSet::new();
+    for record in snapshot.unravel_history.iter() {
+        let tile = record.tile_id as usize;
+        if tile >= board.words().len() || !board.is_word_unravelled(tile) || !tiles.insert(tile) {
+            return Err(GameError::SnapshotInvalidHistory);
+        }
+    }
+    Ok(())
+}
+
+impl<S: GameState, P: Player> Game<S, P> {
+    fn from_snapshot(
+        snapshot: GameSnapshot<P>,
+        state: S,
+        clock: Arc<dyn Clock>,
+    ) -> Result<Self, GameError> {
+        snapshot.check_version()?;
+        if snapshot.started != state.has_started() {
+            return Err(GameError::SnapshotStateMismatch);
+        }
+        check_snapshot(&snapshot)?;
+        let team_one_players: BTreeMap<u32, P> = snapshot
+            .team_one_players
+            .into_iter()
+            .map(|p| (*p.get_id(), p))
+            .collect();
+        let team_two_players: BTreeMap<u32, P> = snapshot
+            .team_two_players
+            .into_iter()
+            .map(|p| (*p.get_id(), p))
+            .collect();
+        // the saved team clock was charged for the turn so far. Give the time
+        // back and carry it into the restored turn, so both the team clock and
+        // the turn limit pick up where they left off.
+        let turn_carried = match snapshot.winner {
+            None => snapshot.turn_elapsed,
+            Some(_) => Duration::default(),
+        };
+        let mut team_one_time_left = snapshot.team_one_time_left;
+        let mut team_two_time_left = snapshot.team_two_time_left;
+        let current_clock = match snapshot.next_turn {
+            Some(Team::TeamOne) => team_one_time_left.as_mut(),
+            Some(Team::TeamTwo) => team_two_time_left.as_mut(),
+            None => None,
+        };
+        if let Some(left) = current_clock {
+            *left += turn_carried;
+        }
+        let turn_started_at = clock.now();
+        Ok(Game {
+            board: snapshot.board,
+            team_one_spymaster_ind: spymaster_ind_for(
+                &team_one_players,
+                snapshot.team_one_spymaster,
+            )?,
+            team_two_spymaster_ind: spymaster_ind_for(
+                &team_two_players,
+                snapshot.team_two_spymaster,
+            )?,
+            team_one_players,
+            team_two_players,
+            spectators: snapshot
+                .spectators
+                .into_iter()
+                .map(|p| (*p.get_id(), p))
+                .collect(),
+            spectators_see_key_after_end: snapshot.spectators_see_key_after_end,
+            team_one_score: snapshot.team_one_score,
+            team_two_score: snapshot.team_two_score,
+            next_turn: snapshot.next_turn,
+            starting_team: snapshot.starting_team,
+            rules: snapshot.rules,
+            current_clue: snapshot.current_clue,
+            guesses_left: snapshot.guesses_left,
+            host_id: snapshot.host_id,
+            winner: snapshot.winner,
+            unravel_history: snapshot.unravel_history,
+            round_history: snapshot.round_history,
+            timers: snapshot.timers,
+            clock,
+            turn_started_at,
+            turn_carried,
+            team_one_time_left,
+            team_two_time_left,
+            player_stats: snapshot.player_stats,
+            presence: BTreeMap::new(),
+            activity: Activity::default(),
+            hooks: Vec::new(),
+            observers: Observers::default(),
+            state,
+        })
+    }
+}
+
 impl<P: Player> Game<InitialGame, P> {
-    pub fn new(vocab: &Vec<String>) -> Result<Self, InvalidError> {
-        let board = Board::new(vocab)?;
+    pub fn new(vocab: &[String], rules: RuleSet) -> Result<Self, GameError> {
+        Game::new_with_hooks(vocab, rules, Vec::new())
+    }
+
+    /// Like `new`, with house rules applied to every unravel in registration
+    /// order. Hooks stay with the game across rematches but are not part of
+    /// snapshots.
+    pub fn new_with_hooks(
+        vocab: &[String],
+        rules: RuleSet,
+        hooks: Vec<Box<dyn RuleHook>>,
+    ) -> Result<Self, GameError> {
+        rules.validate(vocab.len())?;
+        let starting_team = rules.starting_team.pick();
+        let (num_team_one, num_team_two) = rules.card_counts(starting_team);
+        let board = Board::with_card_counts(vocab, num_team_one, num_team_two)?;
         let mut game = Game {
             board,
-            team_one_players: HashMap::new(),
-            team_two_players: HashMap::new(),
+            team_one_players: BTreeMap::new(),
+            team_two_players: BTreeMap::new(),
+            spectators: BTreeMap::new(),
+            spectators_see_key_after_end: false,
             team_one_spymaster_ind: None,
             team_two_spymaster_ind: None,
             team_one_score: 0,
             team_two_score: 0,
             next_turn: None,
+            starting_team,
+            rules,
+            current_clue: None,
+            guesses_left: None,
+            host_id: None,
+            winner: None,
+            unravel_history: Vec::new(),
+            round_history: Vec::new(),
+            timers: TimerConfig::default(),
+            clock: Arc::new(SystemClock::new()),
+            turn_started_at: Duration::default(),
+            turn_carried: Duration::default(),
+            team_one_time_left: None,
+            team_two_time_left: None,
+            player_stats: BTreeMap::new(),
+            presence: BTreeMap::new(),
+            activity: Activity::default(),
+            hooks,
+            observers: Observers::default(),
             state: InitialGame {},
         };
         game.team_one_score = game.board.get_team_one_pending_size().try_into().unwrap();
@@ -112,20 +763,36 @@ impl<P: Player> Game<InitialGame, P> {
         Ok(game)
     }
 
+    /// Bring back a saved game that hadn't begun, timed by `clock`. House
+    /// rules aren't saved; add them again with `add_hook`.
+    pub fn restore(snapshot: GameSnapshot<P>, clock: Arc<dyn Clock>) -> Result<Self, GameError> {
+        Game::from_snapshot(snapshot, InitialGame {}, clock)
+    }
+
+    pub fn set_timers(&mut self, timers: TimerConfig) {
+        self.timers = timers;
+        self.team_one_time_left = self.starting_clock_for(Team::TeamOne);
+        self.team_two_time_left = self.starting_clock_for(Team::TeamTwo);
+    }
+
     pub fn can_begin(&self) -> bool {
         self.has_enough_players()
     }
 
-    pub fn begin(self) -> Result<Game<InProgressGame, P>, InvalidError> {
+    pub fn begin(self) -> Result<Game<InProgressGame, P>, GameError> {
         if self.can_begin() {
             // return (None, Ok(InProgressGame { game: self.game }));
             let mut game = Game::<InProgressGame, P>::from(self);
-            game.next_turn = Some(Team::TeamOne);
+            game.next_turn = Some(game.starting_team);
+            game.turn_started_at = game.clock.now();
+            game.observers.notify(GameEvent::TurnChanged {
+                team: game.starting_team,
+            });
             return Ok(game);
         }
-        return Err(InvalidError::new(
-            "Not enough players. Each team needs 2 players atleast and a chosen spymaster.",
-        ));
+        Err(GameError::NotEnoughPlayers {
+            per_team: self.rules.min_team_size,
+        })
     }
 }
 
@@ -136,113 +803,654 @@ impl<P: Player> From<Game<InitialGame, P>> for Game<InProgressGame, P> {
             board: value.board,
             team_one_players: value.team_one_players,
             team_two_players: value.team_two_players,
+            spectators: value.spectators,
+            spectators_see_key_after_end: value.spectators_see_key_after_end,
             team_one_spymaster_ind: value.team_one_spymaster_ind,
             team_two_spymaster_ind: value.team_two_spymaster_ind,
             team_one_score: value.team_one_score,
             team_two_score: value.team_two_score,
             next_turn: value.next_turn,
+            starting_team: value.starting_team,
+            rules: value.rules,
+            current_clue: value.current_clue,
+            guesses_left: value.guesses_left,
+            host_id: value.host_id,
+            winner: value.winner,
+            unravel_history: value.unravel_history,
+            round_history: value.round_history,
+            timers: value.timers,
+            clock: value.clock,
+            turn_started_at: value.turn_started_at,
+            turn_carried: value.turn_carried,
+            team_one_time_left: value.team_one_time_left,
+            team_two_time_left: value.team_two_time_left,
+            player_stats: value.player_stats,
+            presence: value.presence,
+            activity: value.activity,
+            hooks: value.hooks,
+            observers: value.observers,
         }
     }
 }
 
 impl<P: Player> Game<InProgressGame, P> {
-    pub fn try_unravel(&mut self, player: &P, tile_id: u8) -> Result<MoveResult, InvalidMoveError> {
+    /// Bring back a saved game in progress, timed by `clock`. The turn that
+    /// was running keeps counting from where it was saved. House rules aren't
+    /// saved; add them again with `add_hook`.
+    pub fn restore(snapshot: GameSnapshot<P>, clock: Arc<dyn Clock>) -> Result<Self, GameError> {
+        Game::from_snapshot(snapshot, InProgressGame {}, clock)
+    }
+
+    /// Charge the time spent in the current turn to `team`, drop its clue and
+    /// start a new turn.
+    fn finish_turn(&mut self, team: Team) {
+        let now = self.clock.now();
+        self.finish_turn_at(team, now);
+    }
+
+    /// Like `finish_turn`, for a turn that ended at `end` rather than now.
+    fn finish_turn_at(&mut self, team: Team, end: Duration) {
+        self.current_clue = None;
+        self.guesses_left = None;
+        let spent = end.checked_sub(self.turn_started_at).unwrap_or_default() + self.turn_carried;
+        let time_left = match team {
+            Team::TeamOne => &mut self.team_one_time_left,
+            Team::TeamTwo => &mut self.team_two_time_left,
+        };
+        if let Some(left) = time_left {
+            *left = left.checked_sub(spent).unwrap_or_default();
+        }
+        self.turn_started_at = end;
+        self.turn_carried = Duration::default();
+        self.activity.reset_offer();
+    }
+
+    /// Pass the turn if nobody on `team` did anything for the idle timeout.
+    fn skip_idle_team(&mut self, team: Team) -> bool {
+        let timeout = match self.timers.team_idle {
+            Some(timeout) => timeout,
+            None => return false,
+        };
+        let players = match team {
+            Team::TeamOne => &self.team_one_players,
+            Team::TeamTwo => &self.team_two_players,
+        };
+        let since = self
+            .activity
+            .last_active_of(players.keys())
+            .map_or(self.turn_started_at, |last| last.max(self.turn_started_at));
+        if self.clock.now().checked_sub(since).unwrap_or_default() < timeout {
+            return false;
+        }
+        self.next_turn = Some(other_team(team));
+        // the turn was lost when the timeout ran out, not now.
+        self.finish_turn_at(team, since + timeout);
+        self.observers.notify(GameEvent::TurnSkipped { team });
+        self.observers.notify(GameEvent::TurnChanged {
+            team: other_team(team),
+        });
+        true
+    }
+
+    /// Offer the spymaster role to a teammate if the spymaster owes a clue
+    /// and has been quiet for the idle timeout.
+    fn offer_idle_spymaster(&mut self, team: Team) {
+        let timeout = match self.timers.spymaster_idle {
+            Some(timeout) => timeout,
+            None => return,
+        };
+        if self.current_clue.is_some() || self.activity.offer.is_some() {
+            return;
+        }
+        let (players, spymaster) = match team {
+            Team::TeamOne => (&self.team_one_players, self.get_team_one_spymaster()),
+            Team::TeamTwo => (&self.team_two_players, self.get_team_two_spymaster()),
+        };
+        let spymaster = match spymaster {
+            Some(spymaster) => *spymaster.get_id(),
+            None => return,
+        };
+        let since = self
+            .activity
+            .last_active(spymaster)
+            .map_or(self.turn_started_at, |last| last.max(self.turn_started_at));
+        if self.clock.now().checked_sub(since).unwrap_or_default() < timeout {
+            return;
+        }
+        let candidates: Vec<u32> = players
+            .keys()
+            .filter(|id| **id != spymaster)
+            .cloned()
+            .collect();
+        if let Some(offered_to) = self.activity.pick_successor(&candidates) {
+            let offer = SpymasterOffer {
+                team,
+                idle_spymaster: spymaster,
+                offered_to,
+            };
+            self.activity.offer = Some(offer);
+            self.observers.notify(GameEvent::SpymasterOffered(offer));
+        }
+    }
+
+    /// Take the spymaster role offered to `player`.
+    pub fn accept_spymaster_offer(&mut self, player: &P) -> Result<(), GameError> {
+        let offer = match self.activity.offer {
+            Some(offer) if offer.offered_to == *player.get_id() => offer,
+            _ => return Err(GameError::NoSpymasterOffer),
+        };
+        let players = match offer.team {
+            Team::TeamOne => &self.team_one_players,
+            Team::TeamTwo => &self.team_two_players,
+        };
+        let ind = players
+            .keys()
+            .position(|id| *id == offer.offered_to)
+            .ok_or(GameError::NotInGame)?;
+        match offer.team {
+            Team::TeamOne => self.set_team_one_spymaster(ind)?,
+            Team::TeamTwo => self.set_team_two_spymaster(ind)?,
+        }
+        self.activity.reset_offer();
+        self.record_activity(player);
+        Ok(())
+    }
+
+    /// Turn the offered role down. The next teammate gets the offer.
+    pub fn decline_spymaster_offer(&mut self, player: &P) -> Result<(), GameError> {
+        match self.activity.offer {
+            Some(offer) if offer.offered_to == *player.get_id() => {}
+            _ => return Err(GameError::NoSpymasterOffer),
+        }
+        self.activity.offer = None;
+        self.activity.declined.push(*player.get_id());
+        self.record_activity(player);
+        self.tick();
+        Ok(())
+    }
+
+    /// Apply the timers. Passes the turn when the turn limit runs out and ends
+    /// the game when a team's clock hits zero. Moves call this on their own,
+    /// servers should also call it periodically.
+    pub fn tick(&mut self) -> MoveResult {
+        loop {
+            if let Some((team, reason)) = self.winner {
+                return MoveResult::Win(team, reason);
+            }
+            let team = match self.next_turn {
+                Some(team) => team,
+                None => return MoveResult::Continue,
+            };
+            let elapsed = self.turn_elapsed();
+            let time_left = match team {
+                Team::TeamOne => self.team_one_time_left,
+                Team::TeamTwo => self.team_two_time_left,
+            };
+            if let Some(left) = time_left {
+                // the clock only loses the game if it runs out before the turn would pass.
+                let limit = self.turn_limit_for(team).unwrap_or(left);
+                if elapsed >= left && left <= limit {
+                    match team {
+                        Team::TeamOne => self.team_one_time_left = Some(Duration::default()),
+                        Team::TeamTwo => self.team_two_time_left = Some(Duration::default()),
+                    }
+                    self.winner = Some((other_team(team), WinReason::OpponentOutOfTime));
+                    self.observers.notify(GameEvent::GameWon {
+                        team: other_team(team),
+                        reason: WinReason::OpponentOutOfTime,
+                    });
+                    continue;
+                }
+            }
+            match self.turn_limit_for(team) {
+                Some(limit) if elapsed >= limit => {
+                    // the turn ended when the limit ran out, not now.
+                    let left = time_left.map(|left| left.checked_sub(limit).unwrap_or_default());
+                    match team {
+                        Team::TeamOne => self.team_one_time_left = left,
+                        Team::TeamTwo => self.team_two_time_left = left,
+                    }
+                    // time carried over from a restore is the first part of the turn.
+                    match limit.checked_sub(self.turn_carried) {
+                        Some(rest) => {
+                            self.turn_started_at += rest;
+                            self.turn_carried = Duration::default();
+                        }
+                        None => self.turn_carried -= limit,
+                    }
+                    self.current_clue = None;
+                    self.guesses_left = None;
+                    self.activity.reset_offer();
+                    self.next_turn = Some(other_team(team));
+                    self.observers.notify(GameEvent::TurnChanged {
+                        team: other_team(team),
+                    });
+                }
+                _ => {
+                    if self.skip_idle_team(team) {
+                        continue;
+                    }
+                    self.offer_idle_spymaster(team);
+                    return MoveResult::Continue;
+                }
+            }
+        }
+    }
+
+    /// Reveal a tile for the player's team. The timers run first: if the team's
+    /// clock ran out before the move arrived, the tile stays hidden and the
+    /// win that handed the other team is returned.
+    pub fn try_unravel(&mut self, player: &P, tile_id: u8) -> Result<MoveResult, GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        if self.is_spectator(player) {
+            return Err(GameError::SpectatorCannotAct);
+        }
         let team_num = match self.get_player_team(player) {
             Some(team) => team,
             None => {
-                return Err(InvalidMoveError::new("Player not in the team"));
+                return Err(GameError::NotInGame);
             }
         };
+        if self.is_spymaster_of(player, team_num) {
+            return Err(GameError::SpymasterCannotGuess);
+        }
+        if let MoveResult::Win(team, reason) = self.tick() {
+            return Ok(MoveResult::Win(team, reason));
+        }
 
         let mut move_result: MoveResult = MoveResult::Continue;
+        self.record_activity(player);
 
         if team_num == *self.next_turn.as_ref().unwrap() {
+            if self.rules.guess_limit != GuessLimit::Unlimited && self.current_clue.is_none() {
+                return Err(GameError::WaitingForClue);
+            }
+            let record = UnravelRecord {
+                tile_id,
+                team_one_score: self.team_one_score,
+                team_two_score: self.team_two_score,
+                next_turn: self.next_turn,
+                current_clue: self.current_clue.clone(),
+                guesses_left: self.guesses_left,
+                player_id: Some(*player.get_id()),
+                turn_elapsed: self.turn_elapsed(),
+                team_one_time_left: self.team_one_time_left,
+                team_two_time_left: self.team_two_time_left,
+            };
+            let mut outcome = MoveOutcome {
+                next_turn: team_num,
+                guesses_left: self.guesses_left,
+                winner: None,
+            };
             match self.board.unravel_word(tile_id as usize) {
                 Ok(_) => {
                     if tile_id == self.board.danger_index() {
-                        // handle Game Over.
-                        let win_team = if team_num == Team::TeamOne {
-                            Team::TeamTwo
-                        } else {
-                            Team::TeamOne
-                        };
-                        move_result = MoveResult::Win(win_team, WinReason::OpponentDangerDraw);
+                        match self.rules.assassin {
+                            AssassinRule::LoseGame => {
+                                // handle Game Over.
+                                let win_team = other_team(team_num);
+                                outcome.winner = Some((win_team, WinReason::OpponentDangerDraw));
+                            }
+                            AssassinRule::EndTurn => {
+                                outcome.next_turn = other_team(team_num);
+                            }
+                        }
                     } else if self.board.is_grey_index(tile_id.into()) {
                         // handle grey tile
-                        if team_num == Team::TeamOne {
-                            self.next_turn = Some(Team::TeamTwo);
-                        } else if team_num == Team::TeamTwo {
-                            self.next_turn = Some(Team::TeamOne);
-                        } else {
-                            panic!("unreachable code reached! No team to update for grey tile.");
-                        }
+                        outcome.next_turn = other_team(team_num);
                     } else if self.board.is_team_one_index(tile_id.into()) {
                         if team_num == Team::TeamTwo {
-                            // team one guessed wrong.
-                            self.next_turn = Some(Team::TeamTwo);
+                            // team two guessed wrong.
+                            outcome.next_turn = Team::TeamOne;
                         }
                     } else if self.board.is_team_two_index(tile_id.into()) {
                         if team_num == Team::TeamOne {
-                            // team two guessed wrong.
-                            self.next_turn = Some(Team::TeamTwo);
+                            // team one guessed wrong.
+                            outcome.next_turn = Team::TeamTwo;
                         }
                     } else {
                         // This should be unreachable but covers future changes.
-                        return Err(InvalidMoveError::new(
-                            format!("Couldn't update score for unravelling: {}\n", tile_id)
-                                .as_ref(),
-                        ));
+                        self.board.veil_word(tile_id as usize)?;
+                        return Err(GameError::TileOutOfRange(tile_id as usize));
                     }
                 }
                 Err(e) => {
-                    return Err(InvalidMoveError::new(format!("{:?}", e).as_ref()));
+                    return Err(e);
                 }
             }
 
-            self.team_one_score = self.board.get_team_one_pending_size().try_into().unwrap();
-            self.team_two_score = self.board.get_team_two_pending_size().try_into().unwrap();
+            let team_one_score: u8 = self.board.get_team_one_pending_size().try_into().unwrap();
+            let team_two_score: u8 = self.board.get_team_two_pending_size().try_into().unwrap();
 
-            if self.team_one_score == TARGET_SCORE {
-                move_result = MoveResult::Win(Team::TeamOne, WinReason::ScoreReached);
+            if team_one_score == TARGET_SCORE {
+                outcome.winner = Some((Team::TeamOne, WinReason::ScoreReached));
             }
 
-            if self.team_two_score == TARGET_SCORE {
-                move_result = MoveResult::Win(Team::TeamTwo, WinReason::ScoreReached);
+            if team_two_score == TARGET_SCORE {
+                outcome.winner = Some((Team::TeamTwo, WinReason::ScoreReached));
             }
-            return Ok(move_result);
-        }
-        return Err(InvalidMoveError::new("Not the current team's turn"));
-    }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::players::SimplePlayer;
 
-    fn setup_valid_game() -> Result<Game<InitialGame, SimplePlayer>, InvalidError> {
-        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
-        let mut game = Game::new(&words)?;
+            if outcome.next_turn == team_num && outcome.winner.is_none() {
+                if let Some(left) = outcome.guesses_left {
+                    let left = left.saturating_sub(1);
+                    outcome.guesses_left = Some(left);
+                    if left == 0 {
+                        outcome.next_turn = other_team(team_num);
+                    }
+                }
+            }
 
-        game.add_player_team_one(SimplePlayer::new("p1", 1));
-        game.add_player_team_two(SimplePlayer::new("p2", 2));
+            let kind = self.board.tile_kind(tile_id as usize);
+            let guesses_this_turn = self
+                .unravel_history
+                .iter()
+                .rev()
+                .take_while(|r| {
+                    r.next_turn == record.next_turn && r.current_clue == record.current_clue
+                })
+                .count() as u8
+                + 1;
+            let context = MoveContext {
+                player_id: *player.get_id(),
+                team: team_num,
+                tile_id,
+                kind,
+                clue: self.current_clue.as_ref(),
+                guesses_this_turn,
+                rules: &self.rules,
+            };
+            for hook in self.hooks.iter() {
+                hook.on_unravel(&context, &mut outcome);
+            }
 
-        game.add_player_team_one(SimplePlayer::new("p3", 3));
-        game.add_player_team_two(SimplePlayer::new("p4", 4));
+            self.unravel_history.push(record);
+            self.team_one_score = team_one_score;
+            self.team_two_score = team_two_score;
+            self.next_turn = Some(outcome.next_turn);
+            self.guesses_left = outcome.guesses_left;
+            self.winner = outcome.winner;
+            if let Some((team, reason)) = outcome.winner {
+                move_result = MoveResult::Win(team, reason);
+            }
+            if self.next_turn != Some(team_num) || self.is_over() {
+                self.finish_turn(team_num);
+            }
 
-        let res = game.set_team_one_spymaster(0);
-        assert!(res.is_ok());
-        let res = game.set_team_two_spymaster(1);
-        assert!(res.is_ok());
-        assert!(game.can_begin());
-        Ok(game)
+            self.player_stats
+                .entry(*player.get_id())
+                .or_default()
+                .record_reveal(team_num, kind);
+            self.observers.notify(GameEvent::TileUnraveled {
+                player_id: *player.get_id(),
+                team: team_num,
+                tile_id,
+                kind,
+            });
+            if let MoveResult::Win(team, reason) = move_result {
+                self.observers.notify(GameEvent::GameWon { team, reason });
+            } else if let Some(next_team) = self.next_turn.filter(|x| *x != team_num) {
+                self.observers
+                    .notify(GameEvent::TurnChanged { team: next_team });
+            }
+            return Ok(move_result);
+        }
+        Err(GameError::NotYourTurn)
     }
 
-    #[test]
-    fn game_move_team_tracking() -> Result<(), InvalidError> {
-        let game = setup_valid_game()?;
-        let mut game = game.begin()?;
-        assert_eq!(*(game.next_turn.as_ref().unwrap()), Team::TeamOne);
+    /// Take back the most recent unravel. `approver` must be the lobby host
+    /// and the game must still be running. Returns the tile that was hidden again.
+    pub fn undo_last_unravel(&mut self, approver: &P) -> Result<u8, GameError> {
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        if self.host_id != Some(*approver.get_id()) {
+            return Err(GameError::NotHost);
+        }
+        let record = match self.unravel_history.pop() {
+            Some(record) => record,
+            None => {
+                return Err(GameError::NothingToUndo);
+            }
+        };
+        self.board.veil_word(record.tile_id as usize)?;
+        let turn_changed = self.next_turn != record.next_turn;
+        if let (Some(player_id), Some(team)) = (record.player_id, record.next_turn) {
+            if let Some(stats) = self.player_stats.get_mut(&player_id) {
+                stats.revert_reveal(team, self.board.tile_kind(record.tile_id as usize));
+            }
+        }
+        self.team_one_score = record.team_one_score;
+        self.team_two_score = record.team_two_score;
+        self.next_turn = record.next_turn;
+        self.current_clue = record.current_clue;
+        self.guesses_left = record.guesses_left;
+        // give back what ending the turn charged; the turn resumes at the
+        // time it had run when the tile was revealed.
+        self.team_one_time_left = record.team_one_time_left;
+        self.team_two_time_left = record.team_two_time_left;
+        self.turn_started_at = self.clock.now();
+        self.turn_carried = record.turn_elapsed;
+        self.observers.notify(GameEvent::UnravelUndone {
+            tile_id: record.tile_id,
+        });
+        if let Some(team) = record.next_turn.filter(|_| turn_changed) {
+            self.observers.notify(GameEvent::TurnChanged { team });
+        }
+        Ok(record.tile_id)
+    }
+
+    fn is_spymaster_of(&self, player: &P, team: Team) -> bool {
+        let spymaster = match team {
+            Team::TeamOne => self.get_team_one_spymaster(),
+            Team::TeamTwo => self.get_team_two_spymaster(),
+        };
+        spymaster.map(|p| p.get_id()) == Some(player.get_id())
+    }
+
+    /// Give the clue for the current turn. Only the spymaster of the team whose
+    /// turn it is can give one, once per turn. A clue that arrives after the
+    /// team's clock ran out ends the game and fails with `GameOver`; the win is
+    /// in `get_winner`.
+    pub fn give_clue(&mut self, player: &P, word: &str, number: u8) -> Result<(), GameError> {
+        self.tick();
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        let team = self.next_turn.unwrap();
+        if !self.is_spymaster_of(player, team) {
+            return Err(GameError::NotSpymaster);
+        }
+        if self.current_clue.is_some() {
+            return Err(GameError::ClueAlreadyGiven);
+        }
+        let word = word.trim();
+        if word.is_empty() {
+            return Err(GameError::EmptyClue);
+        }
+        if self
+            .board
+            .words()
+            .iter()
+            .any(|x| x.eq_ignore_ascii_case(word))
+        {
+            return Err(GameError::ClueOnBoard);
+        }
+        let clue = Clue {
+            team,
+            word: String::from(word),
+            number,
+        };
+        self.current_clue = Some(clue.clone());
+        // a clue answers any offer made while the spymaster was quiet.
+        self.activity.reset_offer();
+        self.record_activity(player);
+        self.guesses_left = match self.rules.guess_limit {
+            GuessLimit::Unlimited => None,
+            GuessLimit::ClueNumberPlusOne => Some(number.saturating_add(1)),
+        };
+        self.player_stats
+            .entry(*player.get_id())
+            .or_default()
+            .record_clue(number);
+        self.observers.notify(GameEvent::ClueGiven(clue));
+        Ok(())
+    }
+
+    /// Stop guessing and hand the turn to the other team. Like `give_clue`, fails
+    /// with `GameOver` if the team's clock ran out first.
+    pub fn end_turn(&mut self, player: &P) -> Result<(), GameError> {
+        self.tick();
+        if self.is_over() {
+            return Err(GameError::GameOver);
+        }
+        let team = self.next_turn.unwrap();
+        if self.get_player_team(player) != Some(team) {
+            return Err(GameError::NotYourTurn);
+        }
+        self.record_activity(player);
+        self.next_turn = Some(other_team(team));
+        self.finish_turn(team);
+        self.observers.notify(GameEvent::TurnChanged {
+            team: other_team(team),
+        });
+        Ok(())
+    }
+
+    /// Deal a fresh board for the same lobby once this game is over. Rosters and
+    /// host carry over and the finished game is archived in the round history.
+    pub fn rematch(
+        self,
+        vocab: &[String],
+        options: RematchOptions,
+    ) -> Result<Game<InitialGame, P>, GameError> {
+        let result = match self.result() {
+            Some(result) => result,
+            None => {
+                return Err(GameError::GameNotOver);
+            }
+        };
+        let team_one_time_left = self.starting_clock_for(Team::TeamOne);
+        let team_two_time_left = self.starting_clock_for(Team::TeamTwo);
+        let mut round_history = self.round_history;
+        let starting_team = if options.losing_team_starts {
+            other_team(result.winner)
+        } else {
+            self.rules.starting_team.pick()
+        };
+        let (num_team_one, num_team_two) = self.rules.card_counts(starting_team);
+        let board = Board::with_card_counts(vocab, num_team_one, num_team_two)?;
+        round_history.push(result);
+
+        let mut team_one_spymaster_ind = self.team_one_spymaster_ind;
+        let mut team_two_spymaster_ind = self.team_two_spymaster_ind;
+        if options.rotate_spymasters {
+            let team_one_size = self.team_one_players.len();
+            let team_two_size = self.team_two_players.len();
+            team_one_spymaster_ind = team_one_spymaster_ind.map(|ind| (ind + 1) % team_one_size);
+            team_two_spymaster_ind = team_two_spymaster_ind.map(|ind| (ind + 1) % team_two_size);
+        }
+
+        let mut game = Game {
+            board,
+            team_one_players: self.team_one_players,
+            team_two_players: self.team_two_players,
+            spectators: self.spectators,
+            spectators_see_key_after_end: self.spectators_see_key_after_end,
+            team_one_spymaster_ind,
+            team_two_spymaster_ind,
+            team_one_score: 0,
+            team_two_score: 0,
+            next_turn: None,
+            starting_team,
+            rules: self.rules,
+            current_clue: None,
+            guesses_left: None,
+            host_id: self.host_id,
+            winner: None,
+            unravel_history: Vec::new(),
+            round_history,
+            timers: self.timers,
+            clock: self.clock,
+            turn_started_at: Duration::default(),
+            turn_carried: Duration::default(),
+            team_one_time_left,
+            team_two_time_left,
+            player_stats: BTreeMap::new(),
+            presence: self.presence,
+            activity: Activity::default(),
+            hooks: self.hooks,
+            observers: self.observers,
+            state: InitialGame {},
+        };
+        game.team_one_score = game.board.get_team_one_pending_size().try_into().unwrap();
+        game.team_two_score = game.board.get_team_two_pending_size().try_into().unwrap();
+        Ok(game)
+    }
+}
+
+/// Games for the tests of this and neighbouring modules.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::{Game, InitialGame};
+    use crate::errors::GameError;
+    use crate::players::SimplePlayer;
+    use crate::rules::RuleSet;
+
+    /// `word-0` to `word-24`.
+    pub(crate) fn words() -> Vec<String> {
+        (0..25).map(|x| format!("word-{}", x)).collect()
+    }
+
+    /// Put p1 and p3 on team one, p2 and p4 on team two, and pick each
+    /// team's spymaster by index.
+    pub(crate) fn seat_players(
+        game: &mut Game<InitialGame, SimplePlayer>,
+        team_one_spymaster: usize,
+        team_two_spymaster: usize,
+    ) -> Result<(), GameError> {
+        game.add_player_team_one(SimplePlayer::new("p1", 1));
+        game.add_player_team_two(SimplePlayer::new("p2", 2));
+        game.add_player_team_one(SimplePlayer::new("p3", 3));
+        game.add_player_team_two(SimplePlayer::new("p4", 4));
+        game.set_team_one_spymaster(team_one_spymaster)?;
+        game.set_team_two_spymaster(team_two_spymaster)
+    }
+
+    /// A game of four ready to begin, see `seat_players`.
+    pub(crate) fn setup_game(
+        rules: RuleSet,
+        team_one_spymaster: usize,
+        team_two_spymaster: usize,
+    ) -> Result<Game<InitialGame, SimplePlayer>, GameError> {
+        let mut game = Game::new(&words(), rules)?;
+        seat_players(&mut game, team_one_spymaster, team_two_spymaster)?;
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{seat_players, setup_game, words};
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::hooks::PerfectClueBonus;
+    use crate::observer::EventLog;
+    use crate::players::SimplePlayer;
+    use crate::rules::StartingTeam;
+
+    fn setup_valid_game() -> Result<Game<InitialGame, SimplePlayer>, GameError> {
+        let game = setup_game(RuleSet::casual(), 1, 1)?;
+        assert!(game.can_begin());
+        Ok(game)
+    }
+
+    #[test]
+    fn game_move_team_tracking() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        let mut game = game.begin()?;
+        assert_eq!(*(game.next_turn.as_ref().unwrap()), Team::TeamOne);
         // maybe player changed their name.
         let p1 = SimplePlayer::new("p-whatever", 1);
         let p2 = SimplePlayer::new("p2", 2);
@@ -257,7 +1465,21 @@ mod tests {
     }
 
     #[test]
-    fn game_move_danger_open() -> Result<(), InvalidError> {
+    fn game_spymaster_cannot_guess() -> Result<(), GameError> {
+        let mut rules = RuleSet::classic();
+        rules.starting_team = StartingTeam::TeamOne;
+        let mut game = setup_game_with_rules(rules)?.begin()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let t1_move = (0..25).find(|x| game.board.is_team_one_index(*x)).unwrap();
+        game.give_clue(&p1, "animal", 1)?;
+        let res = game.try_unravel(&p1, t1_move as u8);
+        assert_eq!(res, Err(GameError::SpymasterCannotGuess));
+        assert!(!game.board.is_word_unravelled(t1_move));
+        Ok(())
+    }
+
+    #[test]
+    fn game_move_danger_open() -> Result<(), GameError> {
         let game = setup_valid_game()?;
         let mut game = game.begin()?;
         assert_eq!(*(game.next_turn.as_ref().unwrap()), Team::TeamOne);
@@ -274,7 +1496,7 @@ mod tests {
     }
 
     #[test]
-    fn game_move_grey_open() -> Result<(), InvalidError> {
+    fn game_move_grey_open() -> Result<(), GameError> {
         let game = setup_valid_game()?;
         let mut game = game.begin()?;
         assert_eq!(*(game.next_turn.as_ref().unwrap()), Team::TeamOne);
@@ -291,7 +1513,7 @@ mod tests {
     }
 
     #[test]
-    fn game_move_correct_incorrect_open() -> Result<(), InvalidError> {
+    fn game_move_correct_incorrect_open() -> Result<(), GameError> {
         let game = setup_valid_game()?;
         let mut game = game.begin()?;
         assert_eq!(*(game.next_turn.as_ref().unwrap()), Team::TeamOne);
@@ -324,7 +1546,26 @@ mod tests {
     }
 
     #[test]
-    fn game_winning() -> Result<(), InvalidError> {
+    fn game_move_team_two_opens_team_one_tile() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?.begin()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let p2 = SimplePlayer::new("p2", 2);
+        game.end_turn(&p1)?;
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        let t1_move = (0..game.board.words().len())
+            .find(|x| game.board.is_team_one_index(*x))
+            .unwrap();
+        let old_t1_score = game.get_team_one_score();
+        let res = game.try_unravel(&p2, t1_move as u8)?;
+        assert_eq!(res, MoveResult::Continue);
+        // team two guessed wrong, so team one plays next.
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+        assert_eq!(old_t1_score - 1, game.get_team_one_score());
+        Ok(())
+    }
+
+    #[test]
+    fn game_winning() -> Result<(), GameError> {
         let game = setup_valid_game()?;
         let mut game = game.begin()?;
         assert_eq!(*(game.next_turn.as_ref().unwrap()), Team::TeamOne);
@@ -352,9 +1593,647 @@ mod tests {
     }
 
     #[test]
-    fn new_game_core() -> Result<(), InvalidError> {
+    fn game_undo_last_unravel() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let p2 = SimplePlayer::new("p2", 2);
+        game.set_host(&p2)?;
+        let mut game = game.begin()?;
+        let grey_move = (0..game.board.words().len())
+            .find(|x| game.board.is_grey_index(*x))
+            .unwrap() as u8;
+        // nothing to undo yet.
+        assert!(game.undo_last_unravel(&p2).is_err());
+
+        let res = game.try_unravel(&p1, grey_move);
+        assert!(res.is_ok());
+        assert_eq!(game.next_turn.unwrap(), Team::TeamTwo);
+
+        // only the host may approve.
+        assert!(game.undo_last_unravel(&p1).is_err());
+        assert!(game.board.is_word_unravelled(grey_move as usize));
+
+        assert_eq!(game.undo_last_unravel(&p2)?, grey_move);
+        assert!(!game.board.is_word_unravelled(grey_move as usize));
+        assert_eq!(game.next_turn.unwrap(), Team::TeamOne);
+        assert_eq!(game.get_team_one_score(), 8);
+        assert_eq!(game.get_team_two_score(), 8);
+        // the same tile can be played again.
+        assert!(game.try_unravel(&p1, grey_move).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn game_undo_restores_scores() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        game.set_host(&p1)?;
+        let mut game = game.begin()?;
+        let t1_move = (0..game.board.words().len())
+            .find(|x| game.board.is_team_one_index(*x))
+            .unwrap() as u8;
+        let t2_move = (0..game.board.words().len())
+            .find(|x| game.board.is_team_two_index(*x))
+            .unwrap() as u8;
+        game.try_unravel(&p1, t1_move)?;
+        game.try_unravel(&p1, t2_move)?;
+        assert_eq!(game.get_team_one_score(), 7);
+        assert_eq!(game.get_team_two_score(), 7);
+
+        game.undo_last_unravel(&p1)?;
+        assert_eq!(game.get_team_one_score(), 7);
+        assert_eq!(game.get_team_two_score(), 8);
+        assert_eq!(game.next_turn.unwrap(), Team::TeamOne);
+        game.undo_last_unravel(&p1)?;
+        assert_eq!(game.get_team_one_score(), 8);
+        assert_eq!(game.get_team_two_score(), 8);
+        assert!(!game.board.is_word_unravelled(t1_move as usize));
+        Ok(())
+    }
+
+    #[test]
+    fn game_undo_restores_timers() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        game.set_host(&p1)?;
+        let clock = Arc::new(ManualClock::new());
+        game.set_clock(clock.clone());
+        game.set_timers(TimerConfig {
+            turn_limit: Some(Duration::from_secs(60)),
+            team_clock: Some(Duration::from_secs(300)),
+            ..TimerConfig::default()
+        });
+        let mut game = game.begin()?;
+        let grey_move = (0..game.board.words().len())
+            .find(|x| game.board.is_grey_index(*x))
+            .unwrap() as u8;
+        clock.advance(Duration::from_secs(20));
+        game.try_unravel(&p1, grey_move)?;
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(
+            game.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(280))
+        );
+        assert_eq!(
+            game.get_team_time_left(Team::TeamTwo),
+            Some(Duration::from_secs(290))
+        );
+
+        game.undo_last_unravel(&p1)?;
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+        assert_eq!(
+            game.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(280))
+        );
+        assert_eq!(
+            game.get_team_time_left(Team::TeamTwo),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(game.get_turn_time_left(), Some(Duration::from_secs(40)));
+        // the restored turn keeps running.
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(
+            game.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(275))
+        );
+        assert_eq!(game.get_turn_time_left(), Some(Duration::from_secs(35)));
+        Ok(())
+    }
+
+    #[test]
+    fn game_undo_not_allowed_after_end() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        game.set_host(&p1)?;
+        let mut game = game.begin()?;
+        let res = game.try_unravel(&p1, game.board.danger_index());
+        assert!(res.is_ok());
+        assert!(game.is_over());
+        assert!(game.undo_last_unravel(&p1).is_err());
+        assert!(game
+            .board
+            .is_word_unravelled(game.board.danger_index() as usize));
+        // no more moves either.
+        let safe_move = (game.board.danger_index() + 1) % game.board.words().len() as u8;
+        assert!(game.try_unravel(&p1, safe_move).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn game_status_tracking() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        assert_eq!(game.status(), GameStatus::Waiting);
+        let mut game = game.begin()?;
+        assert_eq!(game.status(), GameStatus::InProgress);
+        let p1 = SimplePlayer::new("p1", 1);
+        game.try_unravel(&p1, game.board.danger_index())?;
+        assert_eq!(game.status(), GameStatus::Over);
+        Ok(())
+    }
+
+    #[test]
+    fn game_rematch_keeps_rosters() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let mut game = game.begin()?;
+        let words: Vec<String> = (0..25).map(|x| format!("new-word-{}", x)).collect();
+        game.try_unravel(&p1, game.board.danger_index())?;
+        let old_t1_spymaster = *game.get_team_one_spymaster().unwrap().get_id();
+        let old_t2_spymaster = *game.get_team_two_spymaster().unwrap().get_id();
+
+        let game = game.rematch(&words, RematchOptions::default())?;
+        assert_eq!(game.status(), GameStatus::Waiting);
+        assert_eq!(game.board.words(), &words);
+        assert_eq!(game.get_team_one_score(), 8);
+        assert_eq!(game.get_team_two_score(), 8);
+        assert_eq!(game.get_player_team(&p1), Some(Team::TeamOne));
+        assert_eq!(game.get_team_two_players().len(), 2);
+        assert_eq!(
+            *game.get_team_one_spymaster().unwrap().get_id(),
+            old_t1_spymaster
+        );
+        assert_eq!(
+            *game.get_team_two_spymaster().unwrap().get_id(),
+            old_t2_spymaster
+        );
+        assert_eq!(game.get_round_history().len(), 1);
+        let result = &game.get_round_history()[0];
+        assert_eq!(result.winner, Team::TeamTwo);
+        assert_eq!(result.reason, WinReason::OpponentDangerDraw);
+        assert_eq!(result.team_one_players, vec![1, 3]);
+
+        let game = game.begin()?;
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+        Ok(())
+    }
+
+    #[test]
+    fn game_rematch_rotation_and_losers_start() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let mut game = game.begin()?;
         let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
-        let mut game: Game<InitialGame, SimplePlayer> = Game::new(&words)?;
+        game.try_unravel(&p1, game.board.danger_index())?;
+        let options = RematchOptions {
+            rotate_spymasters: true,
+            losing_team_starts: true,
+        };
+        let game = game.rematch(&words, options)?;
+        // team one: 1 -> 0, team two: 1 -> 0.
+        assert_eq!(*game.get_team_one_spymaster().unwrap().get_id(), 1);
+        assert_eq!(*game.get_team_two_spymaster().unwrap().get_id(), 2);
+        assert_eq!(game.get_starting_team(), Team::TeamOne);
+        let game = game.begin()?;
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+        Ok(())
+    }
+
+    #[test]
+    fn game_rematch_requires_finished_game() -> Result<(), GameError> {
+        let game = setup_valid_game()?.begin()?;
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        assert!(game.rematch(&words, RematchOptions::default()).is_err());
+        Ok(())
+    }
+
+    fn setup_timed_game(
+        timers: TimerConfig,
+    ) -> Result<(Game<InProgressGame, SimplePlayer>, Arc<ManualClock>), GameError> {
+        let mut game = setup_valid_game()?;
+        let clock = Arc::new(ManualClock::new());
+        game.set_clock(clock.clone());
+        game.set_timers(timers);
+        Ok((game.begin()?, clock))
+    }
+
+    #[test]
+    fn game_turn_limit_passes_turn() -> Result<(), GameError> {
+        let timers = TimerConfig {
+            turn_limit: Some(Duration::from_secs(60)),
+            team_clock: None,
+            ..TimerConfig::default()
+        };
+        let (mut game, clock) = setup_timed_game(timers)?;
+        let p1 = SimplePlayer::new("p1", 1);
+        clock.advance(Duration::from_secs(45));
+        assert_eq!(game.tick(), MoveResult::Continue);
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+        assert_eq!(game.get_turn_time_left(), Some(Duration::from_secs(15)));
+
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(game.tick(), MoveResult::Continue);
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        assert_eq!(game.get_turn_time_left(), Some(Duration::from_secs(60)));
+        // team one missed its turn.
+        let safe_move = (game.board.danger_index() + 1) % game.board.words().len() as u8;
+        assert!(game.try_unravel(&p1, safe_move).is_err());
+
+        // two whole turns pass without anyone ticking.
+        clock.advance(Duration::from_secs(130));
+        game.tick();
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        assert_eq!(game.get_turn_time_left(), Some(Duration::from_secs(50)));
+        Ok(())
+    }
+
+    #[test]
+    fn game_idle_spymaster_role_is_offered() -> Result<(), GameError> {
+        let timers = TimerConfig {
+            spymaster_idle: Some(Duration::from_secs(30)),
+            ..TimerConfig::default()
+        };
+        let (mut game, clock) = setup_timed_game(timers)?;
+        let log = EventLog::new();
+        game.add_observer(Box::new(log.clone()));
+        let p1 = SimplePlayer::new("p1", 1);
+        let p2 = SimplePlayer::new("p2", 2);
+        let p3 = SimplePlayer::new("p3", 3);
+        clock.advance(Duration::from_secs(29));
+        game.tick();
+        assert_eq!(game.get_spymaster_offer(), None);
+        clock.advance(Duration::from_secs(1));
+        game.tick();
+        let offer = SpymasterOffer {
+            team: Team::TeamOne,
+            idle_spymaster: 3,
+            offered_to: 1,
+        };
+        assert_eq!(game.get_spymaster_offer(), Some(&offer));
+        assert_eq!(log.events(), vec![GameEvent::SpymasterOffered(offer)]);
+
+        // the only teammate declines, so nobody else is asked this turn.
+        assert_eq!(
+            game.accept_spymaster_offer(&p3),
+            Err(GameError::NoSpymasterOffer)
+        );
+        game.decline_spymaster_offer(&p1)?;
+        assert_eq!(game.get_spymaster_offer(), None);
+
+        // a new turn, a new chance.
+        game.end_turn(&p1)?;
+        game.end_turn(&p2)?;
+        clock.advance(Duration::from_secs(30));
+        game.tick();
+        assert_eq!(game.get_spymaster_offer(), Some(&offer));
+        game.accept_spymaster_offer(&p1)?;
+        assert_eq!(game.get_team_one_spymaster(), Some(&p1));
+        assert_eq!(game.get_spymaster_offer(), None);
+        game.give_clue(&p1, "clue", 2)?;
+        Ok(())
+    }
+
+    #[test]
+    fn game_idle_team_loses_turn() -> Result<(), GameError> {
+        let timers = TimerConfig {
+            team_idle: Some(Duration::from_secs(60)),
+            ..TimerConfig::default()
+        };
+        let (mut game, clock) = setup_timed_game(timers)?;
+        let log = EventLog::new();
+        game.add_observer(Box::new(log.clone()));
+        let p3 = SimplePlayer::new("p3", 3);
+        clock.advance(Duration::from_secs(40));
+        game.record_activity(&p3);
+        assert_eq!(game.get_idle_time(&p3), Some(Duration::from_secs(0)));
+        clock.advance(Duration::from_secs(59));
+        assert_eq!(game.tick(), MoveResult::Continue);
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+        clock.advance(Duration::from_secs(1));
+        game.tick();
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        assert_eq!(
+            log.events(),
+            vec![
+                GameEvent::TurnSkipped {
+                    team: Team::TeamOne
+                },
+                GameEvent::TurnChanged {
+                    team: Team::TeamTwo
+                },
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn game_idle_skip_charges_only_the_timeout() -> Result<(), GameError> {
+        let timers = TimerConfig {
+            turn_limit: None,
+            team_clock: Some(Duration::from_secs(300)),
+            team_idle: Some(Duration::from_secs(60)),
+            ..TimerConfig::default()
+        };
+        let (mut game, clock) = setup_timed_game(timers)?;
+        // nobody ticks until well after the timeout.
+        clock.advance(Duration::from_secs(90));
+        game.tick();
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        assert_eq!(
+            game.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(240))
+        );
+        assert_eq!(
+            game.get_team_time_left(Team::TeamTwo),
+            Some(Duration::from_secs(270))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn game_team_clock_runs_out() -> Result<(), GameError> {
+        let timers = TimerConfig {
+            turn_limit: None,
+            team_clock: Some(Duration::from_secs(300)),
+            ..TimerConfig::default()
+        };
+        let (mut game, clock) = setup_timed_game(timers)?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let grey_move = (0..game.board.words().len())
+            .find(|x| game.board.is_grey_index(*x))
+            .unwrap() as u8;
+        clock.advance(Duration::from_secs(100));
+        game.try_unravel(&p1, grey_move)?;
+        assert_eq!(
+            game.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(200))
+        );
+        // team two's clock only runs on its own turn.
+        clock.advance(Duration::from_secs(299));
+        assert_eq!(game.tick(), MoveResult::Continue);
+        assert_eq!(
+            game.get_team_time_left(Team::TeamTwo),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            game.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(200))
+        );
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(
+            game.tick(),
+            MoveResult::Win(Team::TeamOne, WinReason::OpponentOutOfTime)
+        );
+        assert!(game.is_over());
+        assert_eq!(
+            game.get_team_time_left(Team::TeamTwo),
+            Some(Duration::default())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn game_late_move_reports_clock_win() -> Result<(), GameError> {
+        let timers = TimerConfig {
+            turn_limit: None,
+            team_clock: Some(Duration::from_secs(60)),
+            ..TimerConfig::default()
+        };
+        let (mut game, clock) = setup_timed_game(timers)?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let t1_move = (0..game.board.words().len())
+            .find(|x| game.board.is_team_one_index(*x))
+            .unwrap();
+        clock.advance(Duration::from_secs(61));
+        // the move that finds the clock empty gets the win, not an error.
+        assert_eq!(
+            game.try_unravel(&p1, t1_move as u8),
+            Ok(MoveResult::Win(Team::TeamTwo, WinReason::OpponentOutOfTime))
+        );
+        assert!(!game.board.is_word_unravelled(t1_move));
+        assert_eq!(
+            game.try_unravel(&p1, t1_move as u8),
+            Err(GameError::GameOver)
+        );
+
+        let (mut game, clock) = setup_timed_game(timers)?;
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(game.end_turn(&p1), Err(GameError::GameOver));
+        assert_eq!(
+            game.get_winner(),
+            Some((Team::TeamTwo, WinReason::OpponentOutOfTime))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn game_turn_limit_charges_team_clock() -> Result<(), GameError> {
+        let timers = TimerConfig {
+            turn_limit: Some(Duration::from_secs(60)),
+            team_clock: Some(Duration::from_secs(100)),
+            ..TimerConfig::default()
+        };
+        let (mut game, clock) = setup_timed_game(timers)?;
+        clock.advance(Duration::from_secs(60));
+        game.tick();
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        assert_eq!(
+            game.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(40))
+        );
+        clock.advance(Duration::from_secs(60));
+        game.tick();
+        // team one only has 40s left, less than a full turn.
+        clock.advance(Duration::from_secs(40));
+        assert_eq!(
+            game.tick(),
+            MoveResult::Win(Team::TeamTwo, WinReason::OpponentOutOfTime)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn game_spectators() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let s1 = SimplePlayer::new("s1", 10);
+        game.add_spectator(s1.clone())?;
+        // team players can't spectate.
+        assert!(game.add_spectator(SimplePlayer::new("p1", 1)).is_err());
+        assert!(game.is_spectator(&s1));
+        assert_eq!(game.get_player_team(&s1), None);
+        // spectators can't host either.
+        assert!(game.set_host(&s1).is_err());
+
+        let mut game = game.begin()?;
+        let safe_move = (game.board.danger_index() + 1) % game.board.words().len() as u8;
+        assert!(game.try_unravel(&s1, safe_move).is_err());
+        assert!(!game.board.is_word_unravelled(safe_move as usize));
+        match game.spectator_view() {
+            SpectatorView::Plain(view) => assert_eq!(view, game.player_view()),
+            SpectatorView::Full(_) => panic!("spectators must not see the key card"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn game_spectators_see_key_after_end() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        game.add_spectator(SimplePlayer::new("s1", 10))?;
+        game.set_spectators_see_key_after_end(true);
+        let mut game = game.begin()?;
+        assert!(matches!(game.spectator_view(), SpectatorView::Plain(_)));
+        let p1 = SimplePlayer::new("p1", 1);
+        game.try_unravel(&p1, game.board.danger_index())?;
+        match game.spectator_view() {
+            SpectatorView::Full(view) => assert_eq!(view, game.spymaster_view()),
+            SpectatorView::Plain(_) => panic!("key card should be visible after the end"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn game_spectator_joins_team() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let s1 = SimplePlayer::new("s1", 10);
+        game.add_spectator(s1.clone())?;
+        game.add_player_team_two(s1.clone());
+        assert!(!game.is_spectator(&s1));
+        assert_eq!(game.get_player_team(&s1), Some(Team::TeamTwo));
+        Ok(())
+    }
+
+    #[test]
+    fn game_player_switches_team() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let p3 = SimplePlayer::new("p3", 3);
+        let p4 = SimplePlayer::new("p4", 4);
+        // the team one spymaster leaves the role behind.
+        game.add_player_team_two(p3.clone());
+        assert_eq!(game.get_player_team(&p3), Some(Team::TeamTwo));
+        assert_eq!(game.get_team_one_players(), vec![&p1]);
+        assert_eq!(game.get_team_one_spymaster(), None);
+        assert_eq!(game.get_team_two_spymaster(), Some(&p4));
+        assert!(!game.can_begin());
+
+        // a lower id joining doesn't move the role either.
+        game.add_player_team_two(p1.clone());
+        assert!(game.get_team_one_players().is_empty());
+        assert_eq!(game.get_team_two_players().len(), 4);
+        assert_eq!(game.get_team_two_spymaster(), Some(&p4));
+        Ok(())
+    }
+
+    fn setup_game_with_rules(rules: RuleSet) -> Result<Game<InitialGame, SimplePlayer>, GameError> {
+        setup_game(rules, 0, 0)
+    }
+
+    #[test]
+    fn game_rules_card_counts_and_start() -> Result<(), GameError> {
+        let mut rules = RuleSet::classic();
+        rules.starting_team = StartingTeam::TeamTwo;
+        let game = setup_game_with_rules(rules)?;
+        assert_eq!(game.get_team_one_score(), 8);
+        assert_eq!(game.get_team_two_score(), 9);
+        let game = game.begin()?;
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+
+        // random start still gives the starting team the extra card.
+        let game = setup_game_with_rules(RuleSet::classic())?;
+        let (starting, other) = match game.get_starting_team() {
+            Team::TeamOne => (game.get_team_one_score(), game.get_team_two_score()),
+            Team::TeamTwo => (game.get_team_two_score(), game.get_team_one_score()),
+        };
+        assert_eq!((starting, other), (9, 8));
+        Ok(())
+    }
+
+    #[test]
+    fn game_rules_validated_on_creation() {
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        let mut rules = RuleSet::casual();
+        rules.min_team_size = 0;
+        assert!(Game::<InitialGame, SimplePlayer>::new(&words, rules).is_err());
+    }
+
+    #[test]
+    fn game_rules_min_team_size() -> Result<(), GameError> {
+        let mut rules = RuleSet::casual();
+        rules.min_team_size = 3;
+        let mut game = setup_game_with_rules(rules)?;
+        assert!(!game.can_begin());
+        game.add_player_team_one(SimplePlayer::new("p5", 5));
+        game.add_player_team_two(SimplePlayer::new("p6", 6));
+        assert!(game.can_begin());
+        Ok(())
+    }
+
+    #[test]
+    fn game_has_enough_players_needs_both_spymasters() -> Result<(), GameError> {
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        let mut game: Game<InitialGame, SimplePlayer> = Game::new(&words, RuleSet::casual())?;
+        for i in 1..5 {
+            game.add_player_team_one(SimplePlayer::new("p", i * 2 - 1));
+            game.add_player_team_two(SimplePlayer::new("p", i * 2));
+        }
+        game.set_team_one_spymaster(0)?;
+        assert!(!game.has_enough_players());
+        game.set_team_two_spymaster(0)?;
+        assert!(game.has_enough_players());
+        Ok(())
+    }
+
+    #[test]
+    fn game_clue_guess_limit() -> Result<(), GameError> {
+        let mut rules = RuleSet::classic();
+        rules.starting_team = StartingTeam::TeamOne;
+        let mut game = setup_game_with_rules(rules)?.begin()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let p2 = SimplePlayer::new("p2", 2);
+        let p3 = SimplePlayer::new("p3", 3);
+        let t1_moves: Vec<u8> = (0..25)
+            .filter(|x| game.board.is_team_one_index(*x))
+            .map(|x| x as u8)
+            .collect();
+        // no guessing before the clue.
+        assert!(game.try_unravel(&p3, t1_moves[0]).is_err());
+        // only the current spymaster gives clues, and not board words.
+        assert!(game.give_clue(&p3, "animal", 1).is_err());
+        assert!(game.give_clue(&p2, "animal", 1).is_err());
+        assert!(game.give_clue(&p1, "WORD-3", 1).is_err());
+        game.give_clue(&p1, "animal", 1)?;
+        assert!(game.give_clue(&p1, "again", 1).is_err());
+        assert_eq!(game.get_guesses_left(), Some(2));
+        assert_eq!(game.get_current_clue().unwrap().word, "animal");
+
+        game.try_unravel(&p3, t1_moves[0])?;
+        assert_eq!(game.get_guesses_left(), Some(1));
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+        game.try_unravel(&p3, t1_moves[1])?;
+        // out of guesses.
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        assert!(game.get_current_clue().is_none());
+        assert_eq!(game.get_guesses_left(), None);
+        Ok(())
+    }
+
+    #[test]
+    fn game_end_turn() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?.begin()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let p2 = SimplePlayer::new("p2", 2);
+        assert!(game.end_turn(&p2).is_err());
+        game.end_turn(&p1)?;
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        Ok(())
+    }
+
+    #[test]
+    fn game_assassin_ends_turn() -> Result<(), GameError> {
+        let mut rules = RuleSet::casual();
+        rules.assassin = AssassinRule::EndTurn;
+        let mut game = setup_game_with_rules(rules)?.begin()?;
+        let p3 = SimplePlayer::new("p3", 3);
+        let res = game.try_unravel(&p3, game.board.danger_index())?;
+        assert_eq!(res, MoveResult::Continue);
+        assert!(!game.is_over());
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        Ok(())
+    }
+
+    #[test]
+    fn new_game_core() -> Result<(), GameError> {
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        let mut game: Game<InitialGame, SimplePlayer> = Game::new(&words, RuleSet::casual())?;
 
         assert_eq!(game.get_team_one_score(), 8);
         assert_eq!(game.get_team_two_score(), 8);
@@ -365,9 +2244,9 @@ mod tests {
     }
 
     #[test]
-    fn test_begin_game() -> Result<(), InvalidError> {
+    fn test_begin_game() -> Result<(), GameError> {
         let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
-        let mut game = Game::new(&words)?;
+        let mut game = Game::new(&words, RuleSet::casual())?;
 
         assert_eq!(game.get_team_one_score(), 8);
         assert_eq!(game.get_team_two_score(), 8);
@@ -395,9 +2274,9 @@ mod tests {
     }
 
     #[test]
-    fn test_has_enough_players_core() -> Result<(), InvalidError> {
+    fn test_has_enough_players_core() -> Result<(), GameError> {
         let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
-        let mut game: Game<InitialGame, SimplePlayer> = Game::new(&words)?;
+        let mut game: Game<InitialGame, SimplePlayer> = Game::new(&words, RuleSet::casual())?;
         game.add_player_team_one(SimplePlayer::new("p1", 1));
         game.add_player_team_two(SimplePlayer::new("p2", 2));
         assert!(
@@ -420,4 +2299,163 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn game_player_stats() -> Result<(), GameError> {
+        let mut rules = RuleSet::classic();
+        rules.starting_team = StartingTeam::TeamOne;
+        let mut game = setup_game_with_rules(rules)?.begin()?;
+        let (p1, p2) = (SimplePlayer::new("p1", 1), SimplePlayer::new("p2", 2));
+        let (p3, p4) = (SimplePlayer::new("p3", 3), SimplePlayer::new("p4", 4));
+        game.set_host(&p1)?;
+        let find = |f: &dyn Fn(usize) -> bool| (0..25).find(|x| f(*x)).unwrap() as u8;
+        let team_one_tile = find(&|x| game.get_board().is_team_one_index(x));
+        let team_two_tile = find(&|x| game.get_board().is_team_two_index(x));
+        let grey_tile = find(&|x| game.get_board().is_grey_index(x));
+
+        game.give_clue(&p1, "clue", 2)?;
+        game.try_unravel(&p3, team_one_tile)?;
+        game.try_unravel(&p3, team_two_tile)?;
+        game.give_clue(&p2, "other", 1)?;
+        game.try_unravel(&p4, grey_tile)?;
+
+        let p3_stats = game.get_stats_for(&p3);
+        assert_eq!(p3_stats.correct_reveals, 1);
+        assert_eq!(p3_stats.opponent_tiles_revealed, 1);
+        assert_eq!(game.get_stats_for(&p4).bystanders_hit, 1);
+        assert_eq!(game.get_stats_for(&p1).clues_given, 1);
+        assert_eq!(game.get_stats_for(&p1).average_clue_number(), Some(2.0));
+        assert_eq!(game.get_stats_for(&p2).total_clue_number, 1);
+
+        // undoing a reveal takes it out of the stats again.
+        game.undo_last_unravel(&p1)?;
+        assert_eq!(game.get_stats_for(&p4).bystanders_hit, 0);
+
+        game.try_unravel(&p4, game.get_board().danger_index())?;
+        let result = game.result().unwrap();
+        assert_eq!(result.player_stats[&4].assassins_hit, 1);
+        assert_eq!(result.player_stats[&3], p3_stats);
+        Ok(())
+    }
+
+    #[test]
+    fn game_rule_hooks() -> Result<(), GameError> {
+        let mut rules = RuleSet::classic();
+        rules.starting_team = StartingTeam::TeamOne;
+        rules.assassin = AssassinRule::EndTurn;
+        let hooks: Vec<Box<dyn RuleHook>> = vec![Box::new(PerfectClueBonus)];
+        let mut game = Game::new_with_hooks(&words(), rules, hooks)?;
+        seat_players(&mut game, 0, 0)?;
+        let mut game = game.begin()?;
+        let (p1, p2) = (SimplePlayer::new("p1", 1), SimplePlayer::new("p2", 2));
+        let (p3, p4) = (SimplePlayer::new("p3", 3), SimplePlayer::new("p4", 4));
+        let team_one_tiles: Vec<u8> = (0..25)
+            .filter(|x| game.get_board().is_team_one_index(*x))
+            .map(|x| x as u8)
+            .collect();
+
+        // clue for 1, one correct guess: 2 guesses left, plus the bonus.
+        game.give_clue(&p1, "clue", 1)?;
+        game.try_unravel(&p3, team_one_tiles[0])?;
+        assert_eq!(game.get_guesses_left(), Some(2));
+        game.try_unravel(&p3, team_one_tiles[1])?;
+        assert_eq!(game.get_guesses_left(), Some(1));
+        game.try_unravel(&p3, team_one_tiles[2])?;
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+
+        // the assassin only costs team two its turn.
+        game.give_clue(&p2, "other", 2)?;
+        let res = game.try_unravel(&p4, game.get_board().danger_index())?;
+        assert_eq!(res, MoveResult::Continue);
+        assert!(!game.is_over());
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+        Ok(())
+    }
+
+    #[test]
+    fn game_hooks_added_after_restore() -> Result<(), GameError> {
+        let mut rules = RuleSet::classic();
+        rules.starting_team = StartingTeam::TeamOne;
+        let game = setup_game_with_rules(rules)?.begin()?;
+        let mut game = Game::<InProgressGame, _>::restore(game.snapshot(), game.clock.clone())?;
+        game.add_hook(Box::new(PerfectClueBonus));
+        let tile = (0..25)
+            .find(|x| game.get_board().is_team_one_index(*x))
+            .unwrap() as u8;
+        game.give_clue(&SimplePlayer::new("p1", 1), "clue", 1)?;
+        game.try_unravel(&SimplePlayer::new("p3", 3), tile)?;
+        assert_eq!(game.get_guesses_left(), Some(2));
+        Ok(())
+    }
+
+    #[test]
+    fn game_handicap() -> Result<(), GameError> {
+        let mut rules = RuleSet::classic();
+        rules.starting_team = StartingTeam::TeamOne;
+        rules.handicap = Some(Handicap {
+            team: Team::TeamTwo,
+            fewer_cards: 3,
+            extra_time: Duration::from_secs(20),
+        });
+        let mut game = setup_game_with_rules(rules)?;
+        assert_eq!(game.get_team_one_score(), 9);
+        assert_eq!(game.get_team_two_score(), 5);
+        assert_eq!(game.get_board().get_team_two_pending_size(), 5);
+        game.set_timers(TimerConfig {
+            turn_limit: Some(Duration::from_secs(60)),
+            team_clock: Some(Duration::from_secs(300)),
+            ..TimerConfig::default()
+        });
+        assert_eq!(
+            game.get_team_time_left(Team::TeamTwo),
+            Some(Duration::from_secs(320))
+        );
+        assert_eq!(
+            game.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(game.player_view().handicap, rules.handicap);
+
+        let clock = Arc::new(ManualClock::new());
+        game.set_clock(clock.clone());
+        let mut game = game.begin()?;
+        game.end_turn(&SimplePlayer::new("p1", 1))?;
+        // team two's turns run 20s longer.
+        clock.advance(Duration::from_secs(70));
+        assert_eq!(game.tick(), MoveResult::Continue);
+        assert_eq!(game.get_next_turn(), Some(Team::TeamTwo));
+        clock.advance(Duration::from_secs(10));
+        game.tick();
+        assert_eq!(game.get_next_turn(), Some(Team::TeamOne));
+
+        game.give_clue(&SimplePlayer::new("p1", 1), "clue", 1)?;
+        game.try_unravel(&SimplePlayer::new("p3", 3), game.get_board().danger_index())?;
+        assert_eq!(game.result().unwrap().handicap, rules.handicap);
+        Ok(())
+    }
+
+    #[test]
+    fn game_typed_errors() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?.begin()?;
+        let (p1, p2) = (SimplePlayer::new("p1", 1), SimplePlayer::new("p2", 2));
+        assert_eq!(game.try_unravel(&p2, 0), Err(GameError::NotYourTurn));
+        assert_eq!(
+            game.try_unravel(&p1, 40),
+            Err(GameError::TileOutOfRange(40))
+        );
+        let grey = (0..25).find(|x| game.board.is_grey_index(*x)).unwrap() as u8;
+        game.try_unravel(&p1, grey)?;
+        assert_eq!(
+            game.try_unravel(&p2, grey),
+            Err(GameError::TileAlreadyRevealed(grey as usize))
+        );
+        assert_eq!(game.undo_last_unravel(&p2), Err(GameError::NotHost));
+        assert_eq!(
+            game.try_unravel(&SimplePlayer::new("stranger", 99), 1)
+                .unwrap_err()
+                .code(),
+            "not_in_game"
+        );
+        Ok(())
+    }
 }
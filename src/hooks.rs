@@ -0,0 +1,128 @@
+use crate::board::TileKind;
+use crate::game::{Clue, Team, WinReason};
+use crate::rules::RuleSet;
+use std::fmt::Debug;
+
+/// What a hook gets to look at for an unravel. The tile is already revealed.
+#[derive(Debug)]
+pub struct MoveContext<'a> {
+    pub player_id: u32,
+    pub team: Team,
+    pub tile_id: u8,
+    pub kind: TileKind,
+    pub clue: Option<&'a Clue>,
+    /// Unravels by `team` this turn, this one included.
+    pub guesses_this_turn: u8,
+    pub rules: &'a RuleSet,
+}
+
+impl<'a> MoveContext<'a> {
+    /// Whether the revealed tile belongs to the guessing team.
+    pub fn is_own_tile(&self) -> bool {
+        matches!(
+            (self.team, self.kind),
+            (Team::TeamOne, TileKind::TeamOne) | (Team::TeamTwo, TileKind::TeamTwo)
+        )
+    }
+}
+
+/// The result of an unravel under the built-in rules, before it is applied
+/// to the game. Hooks may change any of it. Scores aren't part of it: they
+/// always count the cards left on the board, so a hook can't take points
+/// away or hand them out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MoveOutcome {
+    pub next_turn: Team,
+    pub guesses_left: Option<u8>,
+    pub winner: Option<(Team, WinReason)>,
+}
+
+/// A house rule. Hooks run in the order they were registered, each one seeing
+/// the outcome as left by the previous one.
+pub trait RuleHook: Debug + Send + Sync {
+    fn on_unravel(&self, context: &MoveContext, outcome: &mut MoveOutcome);
+}
+
+/// One extra guess once a team has found as many of its tiles as the clue
+/// number. Only matters when guesses are limited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PerfectClueBonus;
+
+impl RuleHook for PerfectClueBonus {
+    fn on_unravel(&self, context: &MoveContext, outcome: &mut MoveOutcome) {
+        let clue = match context.clue {
+            Some(clue) => clue,
+            None => return,
+        };
+        if clue.number == 0
+            || !context.is_own_tile()
+            || context.guesses_this_turn != clue.number
+            || outcome.winner.is_some()
+        {
+            return;
+        }
+        if let Some(left) = outcome.guesses_left {
+            outcome.guesses_left = Some(left.saturating_add(1));
+            outcome.next_turn = context.team;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context<'a>(
+        kind: TileKind,
+        clue: Option<&'a Clue>,
+        guesses_this_turn: u8,
+        rules: &'a RuleSet,
+    ) -> MoveContext<'a> {
+        MoveContext {
+            player_id: 3,
+            team: Team::TeamOne,
+            tile_id: 0,
+            kind,
+            clue,
+            guesses_this_turn,
+            rules,
+        }
+    }
+
+    #[test]
+    fn hooks_perfect_clue_bonus() {
+        let rules = RuleSet::classic();
+        let clue = Clue {
+            team: Team::TeamOne,
+            word: String::from("clue"),
+            number: 2,
+        };
+        let mut outcome = MoveOutcome {
+            next_turn: Team::TeamOne,
+            guesses_left: Some(2),
+            winner: None,
+        };
+        PerfectClueBonus.on_unravel(
+            &context(TileKind::TeamOne, Some(&clue), 1, &rules),
+            &mut outcome,
+        );
+        assert_eq!(outcome.guesses_left, Some(2));
+        outcome.guesses_left = Some(1);
+        PerfectClueBonus.on_unravel(
+            &context(TileKind::TeamOne, Some(&clue), 2, &rules),
+            &mut outcome,
+        );
+        assert_eq!(outcome.guesses_left, Some(2));
+        // a wrong tile never earns the bonus.
+        let mut outcome = MoveOutcome {
+            next_turn: Team::TeamTwo,
+            guesses_left: None,
+            winner: None,
+        };
+        PerfectClueBonus.on_unravel(
+            &context(TileKind::Grey, Some(&clue), 2, &rules),
+            &mut outcome,
+        );
+        assert_eq!(outcome.next_turn, Team::TeamTwo);
+    }
+}
@@ -0,0 +1,723 @@
+use crate::errors::GameError;
+use crate::game::{Team, WinReason};
+use crate::observer::{GameEvent, RosterChange};
+use crate::players::Presence;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    En,
+    De,
+    Es,
+}
+
+impl Locale {
+    /// Parse a language tag such as `de` or `es-MX`. Only the language part
+    /// counts.
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let lang = tag.trim().split(['-', '_']).next()?;
+        match lang.to_ascii_lowercase().as_ref() {
+            "en" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::De => "de",
+            Locale::Es => "es",
+        }
+    }
+
+    /// The first supported language of an `Accept-Language` header, in the
+    /// order the client listed them. Quality values are ignored.
+    pub fn from_accept_language(header: &str) -> Option<Locale> {
+        header
+            .split(',')
+            .filter_map(|part| part.split(';').next())
+            .find_map(Locale::from_tag)
+    }
+
+    /// A locale asked for with the request beats the player's preference.
+    pub fn pick(request: Option<Locale>, player: Option<Locale>) -> Locale {
+        request.or(player).unwrap_or_default()
+    }
+}
+
+/// The catalogue entry for `code`, if `locale` has one.
+fn lookup(locale: Locale, code: &str) -> Option<&'static str> {
+    match locale {
+        Locale::En => english(code),
+        Locale::De => german(code),
+        Locale::Es => spanish(code),
+    }
+}
+
+/// Fill `{name}` placeholders in the `locale` template for `code`, falling
+/// back to English and then to the bare code.
+pub fn translate(locale: Locale, code: &str, params: &[(&str, String)]) -> String {
+    let template = match lookup(locale, code).or_else(|| english(code)) {
+        Some(template) => template,
+        None => return String::from(code),
+    };
+    // one pass over the template, so braces in the values stay as they are.
+    let mut message = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        message.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let param = after.find('}').and_then(|end| {
+            params
+                .iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (end, value))
+        });
+        match param {
+            Some((end, value)) => {
+                message.push_str(value);
+                rest = &after[end + 1..];
+            }
+            None => {
+                message.push('{');
+                rest = after;
+            }
+        }
+    }
+    message.push_str(rest);
+    message
+}
+
+/// Something with a catalogue entry.
+pub trait Localized {
+    fn message_code(&self) -> &'static str;
+
+    fn message_params(&self, _locale: Locale) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
+    fn localize(&self, locale: Locale) -> String {
+        translate(locale, self.message_code(), &self.message_params(locale))
+    }
+}
+
+impl Localized for Team {
+    fn message_code(&self) -> &'static str {
+        match self {
+            Team::TeamOne => "team_one",
+            Team::TeamTwo => "team_two",
+        }
+    }
+}
+
+impl Localized for GameError {
+    fn message_code(&self) -> &'static str {
+        self.code()
+    }
+
+    fn message_params(&self, _locale: Locale) -> Vec<(&'static str, String)> {
+        match self {
+            GameError::WrongVocabSize { expected, found } => vec![
+                ("expected", expected.to_string()),
+                ("found", found.to_string()),
+            ],
+            GameError::PositionOutOfRange(pos) => vec![("pos", pos.to_string())],
+            GameError::SpymasterIndexOutOfRange { index, players } => vec![
+                ("index", index.to_string()),
+                ("players", players.to_string()),
+            ],
+            GameError::NotEnoughPlayers { per_team } => vec![("per_team", per_team.to_string())],
+            GameError::TileOutOfRange(idx)
+            | GameError::TileAlreadyRevealed(idx)
+            | GameError::TileNotRevealed(idx) => vec![("tile", idx.to_string())],
+            GameError::UnsupportedSnapshotVersion(version)
+            | GameError::UnsupportedRegistryVersion(version) => {
+                vec![("version", version.to_string())]
+            }
+            GameError::UnknownPlayer(id) | GameError::SnapshotDuplicatePlayer(id) => {
+                vec![("player", id.to_string())]
+            }
+            GameError::InvalidMatchSize(players) => vec![("players", players.to_string())],
+            GameError::Serialization(source) | GameError::Storage(source) => {
+                vec![("source", source.to_string())]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Localized for GameEvent {
+    fn message_code(&self) -> &'static str {
+        match self {
+            GameEvent::TileUnraveled { .. } => "tile_unraveled",
+            GameEvent::UnravelUndone { .. } => "unravel_undone",
+            GameEvent::TurnChanged { .. } => "turn_changed",
+            GameEvent::ClueGiven(_) => "clue_given",
+            GameEvent::GameWon { reason, .. } => match reason {
+                WinReason::ScoreReached => "game_won_score",
+                WinReason::OpponentDangerDraw => "game_won_assassin",
+                WinReason::OpponentOutOfTime => "game_won_time",
+            },
+            GameEvent::RosterChanged { change, .. } => match change {
+                RosterChange::Joined(_) => "player_joined",
+                RosterChange::Spectating => "player_spectating",
+                RosterChange::LeftSpectators => "player_left_spectators",
+                RosterChange::BecameSpymaster(_) => "player_became_spymaster",
+            },
+            GameEvent::SpymasterOffered(_) => "spymaster_offered",
+            GameEvent::TurnSkipped { .. } => "turn_skipped",
+            GameEvent::PresenceChanged { presence, .. } => match presence {
+                Presence::Online => "player_online",
+                Presence::Away => "player_away",
+                Presence::Offline => "player_offline",
+            },
+        }
+    }
+
+    fn message_params(&self, locale: Locale) -> Vec<(&'static str, String)> {
+        match self {
+            GameEvent::TileUnraveled {
+                player_id,
+                team,
+                tile_id,
+                ..
+            } => vec![
+                ("player", player_id.to_string()),
+                ("team", team.localize(locale)),
+                ("tile", tile_id.to_string()),
+            ],
+            GameEvent::UnravelUndone { tile_id } => vec![("tile", tile_id.to_string())],
+            GameEvent::TurnChanged { team }
+            | GameEvent::GameWon { team, .. }
+            | GameEvent::TurnSkipped { team } => {
+                vec![("team", team.localize(locale))]
+            }
+            GameEvent::ClueGiven(clue) => vec![
+                ("team", clue.team.localize(locale)),
+                ("word", clue.word.clone()),
+                ("number", clue.number.to_string()),
+            ],
+            GameEvent::SpymasterOffered(offer) => vec![
+                ("idle", offer.idle_spymaster.to_string()),
+                ("player", offer.offered_to.to_string()),
+                ("team", offer.team.localize(locale)),
+            ],
+            GameEvent::PresenceChanged { player_id, .. } => {
+                vec![("player", player_id.to_string())]
+            }
+            GameEvent::RosterChanged { player_id, change } => {
+                let mut params = vec![("player", player_id.to_string())];
+                if let RosterChange::Joined(team) | RosterChange::BecameSpymaster(team) = change {
+                    params.push(("team", team.localize(locale)));
+                }
+                params
+            }
+        }
+    }
+}
+
+fn english(code: &str) -> Option<&'static str> {
+    let message = match code {
+        "team_one" => "Team one",
+        "team_two" => "Team two",
+
+        "wrong_vocab_size" => "Vocab must be {expected} words, got {found}",
+        "cards_dont_fit" => "Team cards don't fit on the board",
+        "team_too_small" => "Teams need at least 2 players, a spymaster and a guesser",
+        "team_without_cards" => "Each team needs at least one card",
+        "handicap_too_large" => "Handicapped team needs at least one card left",
+        "not_enough_tokens" => "Need at least one turn token",
+        "position_out_of_range" => "Position {pos} is out of range, must be below 32",
+        "best_of_needs_odd_rounds" => "Best-of series needs an odd number of rounds",
+        "first_to_needs_wins" => "First-to series needs at least one win",
+        "series_decided" => "Series is already decided",
+        "already_on_team" => "Player is already on a team",
+        "spymaster_index_out_of_range" => {
+            "Spymaster index {index} exceeds the {players} players on the team"
+        }
+        "host_not_in_game" => "Host must be a player in the game",
+        "invalid_display_name" => {
+            "Names need 1 to 24 characters and can't contain control characters"
+        }
+        "invalid_avatar" => "Avatar colours must look like #a1b2c3",
+        "not_enough_players" => {
+            "Not enough players. Each team needs at least {per_team} and a clue giver"
+        }
+        "game_over" => "Game is already over",
+        "game_not_over" => "Game is not over yet",
+        "game_not_started" => "The game hasn't started yet",
+        "game_already_started" => "The game has already started",
+        "spectator_cannot_act" => "Spectators cannot act",
+        "not_in_game" => "Player is not in the game",
+        "not_your_turn" => "Not your team's turn",
+        "not_spymaster" => "Only the current team's spymaster can give a clue",
+        "spymaster_cannot_guess" => "Spymasters cannot reveal tiles",
+        "not_host" => "Only the host can do that",
+        "waiting_for_clue" => "Waiting for the spymaster's clue",
+        "clue_already_given" => "A clue was already given this turn",
+        "empty_clue" => "Clue can't be empty",
+        "clue_on_board" => "Clue can't be a word on the board",
+        "tile_out_of_range" => "Tile {tile} is not on the board",
+        "tile_already_revealed" => "Tile {tile} is already revealed",
+        "tile_not_revealed" => "Tile {tile} is not revealed",
+        "nothing_to_undo" => "No unravel to undo",
+        "no_spymaster_offer" => "The spymaster role wasn't offered to you",
+        "lobby_not_found" => "No such lobby",
+        "invalid_session" => "Missing, unknown or revoked session token",
+        "session_lobby_mismatch" => "Session token belongs to another lobby",
+        "invalid_username" => "Usernames are 3 to 32 letters, digits, '_', '-' or '.'",
+        "weak_password" => "Passwords need at least 8 characters",
+        "username_taken" => "That username is already taken",
+        "account_exists" => "This player already has an account",
+        "invalid_credentials" => "Wrong username or password",
+        "invalid_match_size" => "Matches need an even number of 4 to 12 players, not {players}",
+        "already_queued" => "You are already in the matchmaking queue",
+        "not_queued" => "You are not in the matchmaking queue",
+        "unknown_player" => "No registered player with id {player}",
+        "unsupported_registry_version" => "Unsupported player registry version: {version}",
+        "storage" => "Couldn't read or write saved data: {source}",
+        "unsupported_snapshot_version" => "Unsupported snapshot version: {version}",
+        "snapshot_state_mismatch" => "Snapshot is for a different game state",
+        "snapshot_spymaster_missing" => "Snapshot spymaster is not on the team",
+        "snapshot_invalid_board" => "Snapshot board is not a valid key card",
+        "snapshot_score_mismatch" => "Snapshot scores don't match the board",
+        "snapshot_duplicate_player" => "Snapshot has player {player} in more than one place",
+        "snapshot_invalid_history" => "Snapshot unravel history doesn't match the board",
+        "snapshot_invalid_turn" => "Snapshot turn doesn't match whether the game is running",
+        "snapshot_host_missing" => "Snapshot host is not on a team",
+        "serialization" => "Bad snapshot data: {source}",
+
+        "tile_unraveled" => "Player {player} of {team} revealed tile {tile}",
+        "unravel_undone" => "Tile {tile} was hidden again",
+        "turn_changed" => "{team} is up",
+        "clue_given" => "{team} clue: {word}, {number}",
+        "game_won_score" => "{team} found all their cards and wins",
+        "game_won_assassin" => "{team} wins, the other team hit the assassin",
+        "game_won_time" => "{team} wins, the other team ran out of time",
+        "player_joined" => "Player {player} joined {team}",
+        "player_spectating" => "Player {player} is watching",
+        "player_left_spectators" => "Player {player} stopped watching",
+        "player_became_spymaster" => "Player {player} is the spymaster of {team}",
+        "player_online" => "Player {player} is online",
+        "player_away" => "Player {player} lost connection, their seat is held",
+        "player_offline" => "Player {player} is gone",
+        "spymaster_offered" => {
+            "Spymaster {idle} of {team} is idle, player {player} is offered the role"
+        }
+        "turn_skipped" => "{team} was idle and loses the turn",
+        _ => return None,
+    };
+    Some(message)
+}
+
+fn german(code: &str) -> Option<&'static str> {
+    let message = match code {
+        "team_one" => "Team eins",
+        "team_two" => "Team zwei",
+
+        "wrong_vocab_size" => "Es werden {expected} Wörter gebraucht, nicht {found}",
+        "cards_dont_fit" => "Die Teamkarten passen nicht auf das Spielfeld",
+        "team_too_small" => {
+            "Teams brauchen mindestens 2 Spieler, einen Geheimdienstchef und einen Rater"
+        }
+        "team_without_cards" => "Jedes Team braucht mindestens eine Karte",
+        "handicap_too_large" => "Dem Team mit Handicap muss mindestens eine Karte bleiben",
+        "not_enough_tokens" => "Es wird mindestens ein Zugplättchen gebraucht",
+        "position_out_of_range" => "Position {pos} ist ungültig, sie muss kleiner als 32 sein",
+        "best_of_needs_odd_rounds" => "Eine Best-of-Serie braucht eine ungerade Rundenzahl",
+        "first_to_needs_wins" => "Eine First-to-Serie braucht mindestens einen Sieg",
+        "series_decided" => "Die Serie ist schon entschieden",
+        "already_on_team" => "Der Spieler ist schon in einem Team",
+        "spymaster_index_out_of_range" => {
+            "Index {index} ist zu groß für die {players} Spieler im Team"
+        }
+        "host_not_in_game" => "Der Gastgeber muss mitspielen",
+        "invalid_display_name" => "Namen brauchen 1 bis 24 Zeichen ohne Steuerzeichen",
+        "invalid_avatar" => "Avatarfarben müssen wie #a1b2c3 aussehen",
+        "not_enough_players" => {
+            "Zu wenige Spieler. Jedes Team braucht mindestens {per_team} und einen Hinweisgeber"
+        }
+        "game_over" => "Das Spiel ist schon vorbei",
+        "game_not_over" => "Das Spiel ist noch nicht vorbei",
+        "game_not_started" => "Das Spiel hat noch nicht begonnen",
+        "game_already_started" => "Das Spiel hat schon begonnen",
+        "spectator_cannot_act" => "Zuschauer können nicht mitspielen",
+        "not_in_game" => "Der Spieler ist nicht im Spiel",
+        "not_your_turn" => "Dein Team ist nicht am Zug",
+        "not_spymaster" => "Nur der Geheimdienstchef des Teams am Zug darf Hinweise geben",
+        "spymaster_cannot_guess" => "Geheimdienstchefs dürfen keine Karten aufdecken",
+        "not_host" => "Das darf nur der Gastgeber",
+        "waiting_for_clue" => "Warte auf den Hinweis des Geheimdienstchefs",
+        "clue_already_given" => "In diesem Zug wurde schon ein Hinweis gegeben",
+        "empty_clue" => "Der Hinweis darf nicht leer sein",
+        "clue_on_board" => "Der Hinweis darf kein Wort auf dem Spielfeld sein",
+        "tile_out_of_range" => "Feld {tile} gibt es nicht",
+        "tile_already_revealed" => "Feld {tile} ist schon aufgedeckt",
+        "tile_not_revealed" => "Feld {tile} ist nicht aufgedeckt",
+        "nothing_to_undo" => "Es gibt nichts rückgängig zu machen",
+        "no_spymaster_offer" => "Dir wurde die Rolle des Geheimdienstchefs nicht angeboten",
+        "lobby_not_found" => "Diese Lobby gibt es nicht",
+        "invalid_session" => "Sitzungstoken fehlt, ist unbekannt oder wurde widerrufen",
+        "session_lobby_mismatch" => "Das Sitzungstoken gehört zu einer anderen Lobby",
+        "invalid_username" => {
+            "Benutzernamen bestehen aus 3 bis 32 Buchstaben, Ziffern, '_', '-' oder '.'"
+        }
+        "weak_password" => "Passwörter brauchen mindestens 8 Zeichen",
+        "username_taken" => "Dieser Benutzername ist schon vergeben",
+        "account_exists" => "Dieser Spieler hat schon ein Konto",
+        "invalid_credentials" => "Falscher Benutzername oder falsches Passwort",
+        "invalid_match_size" => {
+            "Partien brauchen eine gerade Zahl von 4 bis 12 Spielern, nicht {players}"
+        }
+        "already_queued" => "Du bist schon in der Warteschlange",
+        "not_queued" => "Du bist nicht in der Warteschlange",
+        "unknown_player" => "Es gibt keinen registrierten Spieler mit der Nummer {player}",
+        "unsupported_registry_version" => {
+            "Spielerverzeichnis-Version {version} wird nicht unterstützt"
+        }
+        "storage" => "Gespeicherte Daten konnten nicht gelesen oder geschrieben werden: {source}",
+        "unsupported_snapshot_version" => "Spielstand-Version {version} wird nicht unterstützt",
+        "snapshot_state_mismatch" => "Der Spielstand gehört zu einer anderen Spielphase",
+        "snapshot_spymaster_missing" => "Der Geheimdienstchef im Spielstand ist nicht im Team",
+        "snapshot_invalid_board" => "Das Spielfeld im Spielstand ist keine gültige Schlüsselkarte",
+        "snapshot_score_mismatch" => "Die Punkte im Spielstand passen nicht zum Spielfeld",
+        "snapshot_duplicate_player" => "Spieler {player} steht im Spielstand an mehr als einer Stelle",
+        "snapshot_invalid_history" => "Die aufgedeckten Karten im Spielstand passen nicht zum Spielfeld",
+        "snapshot_invalid_turn" => "Der Zug im Spielstand passt nicht zum Spielverlauf",
+        "snapshot_host_missing" => "Der Gastgeber im Spielstand ist in keinem Team",
+        "serialization" => "Ungültiger Spielstand: {source}",
+
+        "tile_unraveled" => "Spieler {player} von {team} hat Feld {tile} aufgedeckt",
+        "unravel_undone" => "Feld {tile} wurde wieder verdeckt",
+        "turn_changed" => "{team} ist am Zug",
+        "clue_given" => "Hinweis für {team}: {word}, {number}",
+        "game_won_score" => "{team} hat alle Karten gefunden und gewinnt",
+        "game_won_assassin" => "{team} gewinnt, das andere Team hat den Attentäter erwischt",
+        "game_won_time" => "{team} gewinnt, dem anderen Team ist die Zeit ausgegangen",
+        "player_joined" => "Spieler {player} ist {team} beigetreten",
+        "player_spectating" => "Spieler {player} schaut zu",
+        "player_left_spectators" => "Spieler {player} schaut nicht mehr zu",
+        "player_became_spymaster" => "Spieler {player} ist Geheimdienstchef von {team}",
+        "player_online" => "Spieler {player} ist online",
+        "player_away" => "Spieler {player} hat die Verbindung verloren, der Platz bleibt frei",
+        "player_offline" => "Spieler {player} ist weg",
+        "spymaster_offered" => "Geheimdienstchef {idle} von {team} ist untätig, Spieler {player} wird die Rolle angeboten",
+        "turn_skipped" => "{team} war untätig und verliert den Zug",
+        _ => return None,
+    };
+    Some(message)
+}
+
+fn spanish(code: &str) -> Option<&'static str> {
+    let message = match code {
+        "team_one" => "Equipo uno",
+        "team_two" => "Equipo dos",
+
+        "wrong_vocab_size" => "Se necesitan {expected} palabras, no {found}",
+        "cards_dont_fit" => "Las cartas de los equipos no caben en el tablero",
+        "team_too_small" => {
+            "Cada equipo necesita al menos 2 jugadores, un jefe de espías y un agente"
+        }
+        "team_without_cards" => "Cada equipo necesita al menos una carta",
+        "handicap_too_large" => "Al equipo con desventaja le debe quedar al menos una carta",
+        "not_enough_tokens" => "Se necesita al menos una ficha de turno",
+        "position_out_of_range" => "La posición {pos} no es válida, debe ser menor que 32",
+        "best_of_needs_odd_rounds" => "Una serie al mejor de necesita un número impar de rondas",
+        "first_to_needs_wins" => "Una serie a N victorias necesita al menos una victoria",
+        "series_decided" => "La serie ya está decidida",
+        "already_on_team" => "El jugador ya está en un equipo",
+        "spymaster_index_out_of_range" => {
+            "El índice {index} supera los {players} jugadores del equipo"
+        }
+        "host_not_in_game" => "El anfitrión tiene que estar en la partida",
+        "invalid_display_name" => {
+            "Los nombres necesitan de 1 a 24 caracteres sin caracteres de control"
+        }
+        "invalid_avatar" => "Los colores de avatar deben tener la forma #a1b2c3",
+        "not_enough_players" => {
+            "Faltan jugadores. Cada equipo necesita al menos {per_team} y alguien que dé pistas"
+        }
+        "game_over" => "La partida ya terminó",
+        "game_not_over" => "La partida aún no ha terminado",
+        "game_not_started" => "La partida aún no ha empezado",
+        "game_already_started" => "La partida ya ha empezado",
+        "spectator_cannot_act" => "Los espectadores no pueden jugar",
+        "not_in_game" => "El jugador no está en la partida",
+        "not_your_turn" => "No es el turno de tu equipo",
+        "not_spymaster" => "Solo el jefe de espías del equipo en turno puede dar pistas",
+        "spymaster_cannot_guess" => "Los jefes de espías no pueden descubrir cartas",
+        "not_host" => "Solo el anfitrión puede hacer eso",
+        "waiting_for_clue" => "Esperando la pista del jefe de espías",
+        "clue_already_given" => "Ya se dio una pista en este turno",
+        "empty_clue" => "La pista no puede estar vacía",
+        "clue_on_board" => "La pista no puede ser una palabra del tablero",
+        "tile_out_of_range" => "La casilla {tile} no está en el tablero",
+        "tile_already_revealed" => "La casilla {tile} ya está descubierta",
+        "tile_not_revealed" => "La casilla {tile} no está descubierta",
+        "nothing_to_undo" => "No hay nada que deshacer",
+        "no_spymaster_offer" => "No se te ofreció el rol de jefe de espías",
+        "lobby_not_found" => "Esa sala no existe",
+        "invalid_session" => "Falta el token de sesión, es desconocido o fue revocado",
+        "session_lobby_mismatch" => "El token de sesión es de otra sala",
+        "invalid_username" => {
+            "Los nombres de usuario tienen de 3 a 32 letras, dígitos, '_', '-' o '.'"
+        }
+        "weak_password" => "Las contraseñas necesitan al menos 8 caracteres",
+        "username_taken" => "Ese nombre de usuario ya está en uso",
+        "account_exists" => "Este jugador ya tiene una cuenta",
+        "invalid_credentials" => "Usuario o contraseña incorrectos",
+        "invalid_match_size" => {
+            "Las partidas necesitan un número par de 4 a 12 jugadores, no {players}"
+        }
+        "already_queued" => "Ya estás en la cola de emparejamiento",
+        "not_queued" => "No estás en la cola de emparejamiento",
+        "unknown_player" => "No hay ningún jugador registrado con el id {player}",
+        "unsupported_registry_version" => {
+            "Versión del registro de jugadores no soportada: {version}"
+        }
+        "storage" => "No se pudieron leer o escribir los datos guardados: {source}",
+        "unsupported_snapshot_version" => "Versión de partida guardada no soportada: {version}",
+        "snapshot_state_mismatch" => "La partida guardada es de otra fase del juego",
+        "snapshot_spymaster_missing" => "El jefe de espías guardado no está en el equipo",
+        "snapshot_invalid_board" => "El tablero guardado no es una tarjeta clave válida",
+        "snapshot_score_mismatch" => "Las puntuaciones guardadas no coinciden con el tablero",
+        "snapshot_duplicate_player" => {
+            "El jugador {player} aparece en más de un lugar en la partida guardada"
+        }
+        "snapshot_invalid_history" => "El historial de cartas guardado no coincide con el tablero",
+        "snapshot_invalid_turn" => "El turno guardado no coincide con el estado de la partida",
+        "snapshot_host_missing" => "El anfitrión guardado no está en ningún equipo",
+        "serialization" => "Partida guardada no válida: {source}",
+
+        "tile_unraveled" => "El jugador {player} de {team} descubrió la casilla {tile}",
+        "unravel_undone" => "La casilla {tile} se volvió a tapar",
+        "turn_changed" => "Turno de {team}",
+        "clue_given" => "Pista para {team}: {word}, {number}",
+        "game_won_score" => "{team} encontró todas sus cartas y gana",
+        "game_won_assassin" => "{team} gana, el otro equipo encontró al asesino",
+        "game_won_time" => "{team} gana, al otro equipo se le acabó el tiempo",
+        "player_joined" => "El jugador {player} se unió a {team}",
+        "player_spectating" => "El jugador {player} está mirando",
+        "player_left_spectators" => "El jugador {player} dejó de mirar",
+        "player_became_spymaster" => "El jugador {player} es el jefe de espías de {team}",
+        "player_online" => "El jugador {player} está conectado",
+        "player_away" => "El jugador {player} perdió la conexión, su puesto se reserva",
+        "player_offline" => "El jugador {player} se fue",
+        "spymaster_offered" => {
+            "El jefe de espías {idle} de {team} está inactivo, se ofrece el rol al jugador {player}"
+        }
+        "turn_skipped" => "{team} estuvo inactivo y pierde el turno",
+        _ => return None,
+    };
+    Some(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::afk::SpymasterOffer;
+    use crate::board::TileKind;
+    use crate::errors::ErrorSource;
+    use crate::game::Clue;
+
+    #[test]
+    fn i18n_locale_selection() {
+        assert_eq!(Locale::from_tag("de-AT"), Some(Locale::De));
+        assert_eq!(Locale::from_tag("ES"), Some(Locale::Es));
+        assert_eq!(Locale::from_tag("fr"), None);
+        assert_eq!(
+            Locale::from_accept_language("fr-CH, fr;q=0.9, es;q=0.8, en;q=0.5"),
+            Some(Locale::Es)
+        );
+        assert_eq!(Locale::pick(None, Some(Locale::De)), Locale::De);
+        assert_eq!(Locale::pick(Some(Locale::Es), Some(Locale::De)), Locale::Es);
+        assert_eq!(Locale::pick(None, None), Locale::En);
+    }
+
+    #[test]
+    fn i18n_errors() {
+        let err = GameError::TileAlreadyRevealed(4);
+        assert_eq!(err.localize(Locale::En), "Tile 4 is already revealed");
+        assert_eq!(err.localize(Locale::De), "Feld 4 ist schon aufgedeckt");
+        assert_eq!(err.to_string(), err.localize(Locale::En));
+    }
+
+    #[test]
+    fn i18n_events() {
+        let event = GameEvent::ClueGiven(Clue {
+            team: Team::TeamTwo,
+            word: String::from("ocean"),
+            number: 3,
+        });
+        assert_eq!(event.localize(Locale::En), "Team two clue: ocean, 3");
+        assert_eq!(
+            event.localize(Locale::Es),
+            "Pista para Equipo dos: ocean, 3"
+        );
+        // a clue that looks like a placeholder is left alone.
+        let event = GameEvent::ClueGiven(Clue {
+            team: Team::TeamOne,
+            word: String::from("{number}"),
+            number: 2,
+        });
+        assert_eq!(event.localize(Locale::En), "Team one clue: {number}, 2");
+    }
+
+    #[test]
+    fn i18n_fallback() {
+        assert_eq!(translate(Locale::De, "no_such_code", &[]), "no_such_code");
+        // every error and event has an English message and a translation.
+        let source = || ErrorSource::new(std::fmt::Error);
+        let errors = vec![
+            GameError::WrongVocabSize {
+                expected: 25,
+                found: 3,
+            },
+            GameError::CardsDontFit,
+            GameError::TeamTooSmall,
+            GameError::TeamWithoutCards,
+            GameError::HandicapTooLarge,
+            GameError::NotEnoughTokens,
+            GameError::PositionOutOfRange(40),
+            GameError::BestOfNeedsOddRounds,
+            GameError::FirstToNeedsWins,
+            GameError::SeriesDecided,
+            GameError::AlreadyOnTeam,
+            GameError::SpymasterIndexOutOfRange {
+                index: 3,
+                players: 2,
+            },
+            GameError::HostNotInGame,
+            GameError::NotEnoughPlayers { per_team: 2 },
+            GameError::InvalidDisplayName,
+            GameError::InvalidAvatar,
+            GameError::GameOver,
+            GameError::GameNotOver,
+            GameError::GameNotStarted,
+            GameError::GameAlreadyStarted,
+            GameError::SpectatorCannotAct,
+            GameError::NotInGame,
+            GameError::NotYourTurn,
+            GameError::NotSpymaster,
+            GameError::SpymasterCannotGuess,
+            GameError::NotHost,
+            GameError::WaitingForClue,
+            GameError::ClueAlreadyGiven,
+            GameError::EmptyClue,
+            GameError::ClueOnBoard,
+            GameError::TileOutOfRange(30),
+            GameError::TileAlreadyRevealed(4),
+            GameError::TileNotRevealed(4),
+            GameError::NothingToUndo,
+            GameError::NoSpymasterOffer,
+            GameError::LobbyNotFound,
+            GameError::InvalidSession,
+            GameError::SessionLobbyMismatch,
+            GameError::InvalidUsername,
+            GameError::WeakPassword,
+            GameError::UsernameTaken,
+            GameError::AccountExists,
+            GameError::InvalidCredentials,
+            GameError::InvalidMatchSize(3),
+            GameError::AlreadyQueued,
+            GameError::NotQueued,
+            GameError::UnknownPlayer(7),
+            GameError::UnsupportedRegistryVersion(9),
+            GameError::Storage(source()),
+            GameError::UnsupportedSnapshotVersion(9),
+            GameError::SnapshotStateMismatch,
+            GameError::SnapshotSpymasterMissing,
+            GameError::SnapshotInvalidBoard,
+            GameError::SnapshotScoreMismatch,
+            GameError::SnapshotDuplicatePlayer(7),
+            GameError::SnapshotInvalidHistory,
+            GameError::SnapshotInvalidTurn,
+            GameError::SnapshotHostMissing,
+            GameError::Serialization(source()),
+        ];
+        let offer = SpymasterOffer {
+            team: Team::TeamOne,
+            idle_spymaster: 1,
+            offered_to: 3,
+        };
+        let mut events = vec![
+            GameEvent::TileUnraveled {
+                player_id: 1,
+                team: Team::TeamOne,
+                tile_id: 4,
+                kind: TileKind::Grey,
+            },
+            GameEvent::UnravelUndone { tile_id: 4 },
+            GameEvent::TurnChanged {
+                team: Team::TeamTwo,
+            },
+            GameEvent::ClueGiven(Clue {
+                team: Team::TeamOne,
+                word: String::from("ocean"),
+                number: 2,
+            }),
+            GameEvent::SpymasterOffered(offer),
+            GameEvent::TurnSkipped {
+                team: Team::TeamOne,
+            },
+        ];
+        for reason in [
+            WinReason::ScoreReached,
+            WinReason::OpponentDangerDraw,
+            WinReason::OpponentOutOfTime,
+        ]
+        .iter()
+        {
+            events.push(GameEvent::GameWon {
+                team: Team::TeamOne,
+                reason: *reason,
+            });
+        }
+        for change in [
+            RosterChange::Joined(Team::TeamOne),
+            RosterChange::Spectating,
+            RosterChange::LeftSpectators,
+            RosterChange::BecameSpymaster(Team::TeamTwo),
+        ]
+        .iter()
+        {
+            events.push(GameEvent::RosterChanged {
+                player_id: 1,
+                change: *change,
+            });
+        }
+        for presence in [Presence::Online, Presence::Away, Presence::Offline].iter() {
+            events.push(GameEvent::PresenceChanged {
+                player_id: 1,
+                presence: *presence,
+            });
+        }
+        let items: Vec<&dyn Localized> = errors
+            .iter()
+            .map(|e| e as &dyn Localized)
+            .chain(events.iter().map(|e| e as &dyn Localized))
+            .chain(
+                [Team::TeamOne, Team::TeamTwo]
+                    .iter()
+                    .map(|t| t as &dyn Localized),
+            )
+            .collect();
+        for item in items {
+            let code = item.message_code();
+            assert!(english(code).is_some(), "no English message for {}", code);
+            for locale in [Locale::De, Locale::Es].iter() {
+                assert!(
+                    lookup(*locale, code).is_some(),
+                    "{} missing {}",
+                    locale.tag(),
+                    code
+                );
+                let message = item.localize(*locale);
+                assert!(!message.is_empty() && message != code, "{}", code);
+            }
+        }
+    }
+}
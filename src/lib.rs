@@ -1,4 +1,23 @@
+pub mod accounts;
+pub mod afk;
 pub mod board;
+pub mod clock;
+pub mod duet;
 pub mod errors;
 pub mod game;
+pub mod hooks;
+pub mod i18n;
+pub mod matchmaking;
+pub mod observer;
 pub mod players;
+pub mod presence;
+pub mod ratings;
+pub mod registry;
+pub mod rules;
+pub mod series;
+pub mod server;
+pub mod session;
+pub mod snapshot;
+pub mod stats;
+mod storage;
+pub mod views;
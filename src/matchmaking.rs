@@ -0,0 +1,350 @@
+use crate::clock::Clock;
+use crate::errors::GameError;
+use crate::ratings::{self, PlayerRatings};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Larger games make the exhaustive team split too slow.
+pub const MAX_PLAYERS_PER_GAME: usize = 12;
+
+/// How picky the queue is. A player starts out matched only with others
+/// within `initial_spread` rating points. The window grows by `spread_growth`
+/// every `widen_every` they wait. After `max_wait` anyone will do.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MatchmakingConfig {
+    pub players_per_game: usize,
+    pub initial_spread: f64,
+    pub spread_growth: f64,
+    pub widen_every: Duration,
+    pub max_wait: Duration,
+}
+
+impl Default for MatchmakingConfig {
+    fn default() -> Self {
+        MatchmakingConfig {
+            players_per_game: 4,
+            initial_spread: 100.0,
+            spread_growth: 50.0,
+            widen_every: Duration::from_secs(15),
+            max_wait: Duration::from_secs(120),
+        }
+    }
+}
+
+impl MatchmakingConfig {
+    /// Two teams of a spymaster and at least one guesser each.
+    pub fn validate(&self) -> Result<(), GameError> {
+        if self.players_per_game < 4
+            || !self.players_per_game.is_multiple_of(2)
+            || self.players_per_game > MAX_PLAYERS_PER_GAME
+        {
+            return Err(GameError::InvalidMatchSize(self.players_per_game));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueEntry {
+    pub player_id: u32,
+    pub ratings: PlayerRatings,
+    joined_at: Duration,
+}
+
+impl QueueEntry {
+    /// One number to compare players by, whatever role they end up in.
+    pub fn skill(&self) -> f64 {
+        (self.ratings.guesser.value + self.ratings.spymaster.value) / 2.0
+    }
+}
+
+/// Teams for a new game. Both spymasters are also listed in their team.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProposedMatch {
+    pub team_one: Vec<u32>,
+    pub team_two: Vec<u32>,
+    pub team_one_spymaster: u32,
+    pub team_two_spymaster: u32,
+    /// Team ratings the split was balanced on.
+    pub team_one_rating: f64,
+    pub team_two_rating: f64,
+}
+
+/// Players waiting for a game, oldest first.
+#[derive(Debug)]
+pub struct MatchQueue {
+    config: MatchmakingConfig,
+    clock: Arc<dyn Clock>,
+    entries: Vec<QueueEntry>,
+}
+
+impl MatchQueue {
+    pub fn new(config: MatchmakingConfig, clock: Arc<dyn Clock>) -> Result<Self, GameError> {
+        config.validate()?;
+        Ok(MatchQueue {
+            config,
+            clock,
+            entries: Vec::new(),
+        })
+    }
+
+    pub fn get_config(&self) -> &MatchmakingConfig {
+        &self.config
+    }
+
+    pub fn join(&mut self, player_id: u32, ratings: PlayerRatings) -> Result<(), GameError> {
+        if self.position(player_id).is_some() {
+            return Err(GameError::AlreadyQueued);
+        }
+        self.entries.push(QueueEntry {
+            player_id,
+            ratings,
+            joined_at: self.clock.now(),
+        });
+        Ok(())
+    }
+
+    pub fn leave(&mut self, player_id: u32) -> Result<(), GameError> {
+        let position = self.position(player_id).ok_or(GameError::NotQueued)?;
+        self.entries.remove(position);
+        Ok(())
+    }
+
+    /// Place in the queue, starting at 0.
+    pub fn position(&self, player_id: u32) -> Option<usize> {
+        self.entries.iter().position(|e| e.player_id == player_id)
+    }
+
+    pub fn waited(&self, player_id: u32) -> Option<Duration> {
+        let entry = &self.entries[self.position(player_id)?];
+        Some(self.clock.now() - entry.joined_at)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// How far from their own skill a player currently accepts teammates and
+    /// opponents. Infinite once they waited `max_wait`.
+    fn spread(&self, entry: &QueueEntry) -> f64 {
+        let waited = self.clock.now() - entry.joined_at;
+        if waited >= self.config.max_wait {
+            return f64::INFINITY;
+        }
+        let steps = if self.config.widen_every.as_nanos() == 0 {
+            0
+        } else {
+            waited.as_nanos() / self.config.widen_every.as_nanos()
+        };
+        self.config.initial_spread + self.config.spread_growth * steps as f64
+    }
+
+    /// Form every game the queue allows right now. Whoever waited longest is
+    /// served first, together with the closest players both sides accept.
+    pub fn poll(&mut self) -> Vec<ProposedMatch> {
+        let size = self.config.players_per_game;
+        let mut matches = Vec::new();
+        let mut anchor = 0;
+        while anchor < self.entries.len() && self.entries.len() >= size {
+            let first = &self.entries[anchor];
+            let mut candidates: Vec<(usize, f64)> = self
+                .entries
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != anchor)
+                .map(|(i, e)| (i, (e.skill() - first.skill()).abs()))
+                .filter(|(i, distance)| {
+                    *distance <= self.spread(first) && *distance <= self.spread(&self.entries[*i])
+                })
+                .collect();
+            if candidates.len() < size - 1 {
+                anchor += 1;
+                continue;
+            }
+            candidates.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+            let mut picked: Vec<usize> = candidates[..size - 1].iter().map(|c| c.0).collect();
+            picked.push(anchor);
+            picked.sort_unstable();
+            let players: Vec<QueueEntry> = picked
+                .iter()
+                .rev()
+                .map(|i| self.entries.remove(*i))
+                .collect();
+            matches.push(balance(&players));
+        }
+        matches
+    }
+}
+
+/// Split players into the two fairest teams. The two best spymasters get the
+/// role, then every even split of the guessers is tried.
+pub fn balance(players: &[QueueEntry]) -> ProposedMatch {
+    let mut by_spymaster: Vec<&QueueEntry> = players.iter().collect();
+    by_spymaster.sort_by(|a, b| {
+        b.ratings
+            .spymaster
+            .value
+            .total_cmp(&a.ratings.spymaster.value)
+            .then(a.player_id.cmp(&b.player_id))
+    });
+    let (first, second) = (by_spymaster[0], by_spymaster[1]);
+    let guessers: Vec<&QueueEntry> = by_spymaster[2..].to_vec();
+    let rating_of = |id: u32| {
+        players
+            .iter()
+            .find(|p| p.player_id == id)
+            .map(|p| p.ratings)
+            .unwrap_or_default()
+    };
+
+    let mut best: Option<(f64, ProposedMatch)> = None;
+    let half = guessers.len() / 2;
+    for mask in 0u32..(1 << guessers.len()) {
+        if mask.count_ones() as usize != half {
+            continue;
+        }
+        let mut team_one = vec![first.player_id];
+        let mut team_two = vec![second.player_id];
+        for (i, guesser) in guessers.iter().enumerate() {
+            if mask & (1 << i) != 0 {
+                team_one.push(guesser.player_id);
+            } else {
+                team_two.push(guesser.player_id);
+            }
+        }
+        let team_one_rating = ratings::team_rating(Some(first.player_id), &team_one, rating_of);
+        let team_two_rating = ratings::team_rating(Some(second.player_id), &team_two, rating_of);
+        let gap = (team_one_rating - team_two_rating).abs();
+        if best.as_ref().is_none_or(|(best_gap, _)| gap < *best_gap) {
+            team_one.sort_unstable();
+            team_two.sort_unstable();
+            best = Some((
+                gap,
+                ProposedMatch {
+                    team_one,
+                    team_two,
+                    team_one_spymaster: first.player_id,
+                    team_two_spymaster: second.player_id,
+                    team_one_rating,
+                    team_two_rating,
+                },
+            ));
+        }
+    }
+    best.map(|(_, proposed)| proposed)
+        .expect("a game has at least two guessers")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::ratings::Rating;
+
+    fn rated(guesser: f64, spymaster: f64) -> PlayerRatings {
+        PlayerRatings {
+            guesser: Rating {
+                value: guesser,
+                games: 5,
+            },
+            spymaster: Rating {
+                value: spymaster,
+                games: 5,
+            },
+        }
+    }
+
+    fn queue(clock: &Arc<ManualClock>) -> Result<MatchQueue, GameError> {
+        MatchQueue::new(MatchmakingConfig::default(), clock.clone())
+    }
+
+    #[test]
+    fn matchmaking_join_and_leave() -> Result<(), GameError> {
+        let clock = Arc::new(ManualClock::new());
+        let mut queue = queue(&clock)?;
+        queue.join(1, PlayerRatings::default())?;
+        queue.join(2, PlayerRatings::default())?;
+        assert_eq!(
+            queue.join(1, PlayerRatings::default()),
+            Err(GameError::AlreadyQueued)
+        );
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(queue.waited(2), Some(Duration::from_secs(3)));
+        queue.leave(1)?;
+        assert_eq!(queue.leave(1), Err(GameError::NotQueued));
+        assert_eq!(queue.position(2), Some(0));
+        // not enough players yet.
+        assert!(queue.poll().is_empty());
+        assert_eq!(queue.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn matchmaking_balances_teams() -> Result<(), GameError> {
+        let clock = Arc::new(ManualClock::new());
+        let mut queue = queue(&clock)?;
+        queue.join(1, rated(1560.0, 1540.0))?;
+        queue.join(2, rated(1500.0, 1600.0))?;
+        queue.join(3, rated(1450.0, 1500.0))?;
+        queue.join(4, rated(1520.0, 1590.0))?;
+        let matches = queue.poll();
+        assert_eq!(matches.len(), 1);
+        assert!(queue.is_empty());
+        let proposed = &matches[0];
+        // the best two spymasters lead the teams.
+        assert_eq!(proposed.team_one_spymaster, 2);
+        assert_eq!(proposed.team_two_spymaster, 4);
+        // the strongest spymaster gets the weaker guesser.
+        assert_eq!(proposed.team_one, vec![2, 3]);
+        assert_eq!(proposed.team_two, vec![1, 4]);
+        assert!((proposed.team_one_rating - proposed.team_two_rating).abs() <= 50.0);
+        Ok(())
+    }
+
+    #[test]
+    fn matchmaking_widens_with_wait() -> Result<(), GameError> {
+        let clock = Arc::new(ManualClock::new());
+        let mut queue = queue(&clock)?;
+        queue.join(1, rated(1500.0, 1500.0))?;
+        queue.join(2, rated(1500.0, 1500.0))?;
+        queue.join(3, rated(1500.0, 1500.0))?;
+        queue.join(4, rated(1800.0, 1800.0))?;
+        assert!(queue.poll().is_empty());
+        // 300 points apart needs 4 widening steps from both sides.
+        clock.advance(Duration::from_secs(45));
+        assert!(queue.poll().is_empty());
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(queue.poll().len(), 1);
+
+        // anyone goes after the maximum wait.
+        queue.join(5, rated(1000.0, 1000.0))?;
+        queue.join(6, rated(1500.0, 1500.0))?;
+        queue.join(7, rated(1500.0, 1500.0))?;
+        queue.join(8, rated(2500.0, 2500.0))?;
+        clock.advance(Duration::from_secs(119));
+        assert!(queue.poll().is_empty());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(queue.poll().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn matchmaking_config_validation() {
+        let clock: Arc<dyn Clock> = Arc::new(ManualClock::new());
+        for size in [2, 5, 14].iter() {
+            let config = MatchmakingConfig {
+                players_per_game: *size,
+                ..MatchmakingConfig::default()
+            };
+            assert_eq!(
+                MatchQueue::new(config, clock.clone()).err(),
+                Some(GameError::InvalidMatchSize(*size))
+            );
+        }
+    }
+}
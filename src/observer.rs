@@ -0,0 +1,232 @@
+use crate::afk::SpymasterOffer;
+use crate::board::TileKind;
+use crate::game::{Clue, Team, WinReason};
+use crate::players::Presence;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RosterChange {
+    Joined(Team),
+    Spectating,
+    LeftSpectators,
+    BecameSpymaster(Team),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameEvent {
+    TileUnraveled {
+        player_id: u32,
+        team: Team,
+        tile_id: u8,
+        kind: TileKind,
+    },
+    UnravelUndone {
+        tile_id: u8,
+    },
+    TurnChanged {
+        team: Team,
+    },
+    ClueGiven(Clue),
+    GameWon {
+        team: Team,
+        reason: WinReason,
+    },
+    RosterChanged {
+        player_id: u32,
+        change: RosterChange,
+    },
+    PresenceChanged {
+        player_id: u32,
+        presence: Presence,
+    },
+    /// The spymaster went idle and a teammate may take over.
+    SpymasterOffered(SpymasterOffer),
+    /// Nobody on the team did anything before the idle timeout.
+    TurnSkipped {
+        team: Team,
+    },
+}
+
+/// Gets told about everything that happens in a game, synchronously and in
+/// order, right after the game state changed.
+pub trait GameObserver: Send {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+/// The observers registered on a game.
+#[derive(Default)]
+pub struct Observers {
+    observers: Vec<Box<dyn GameObserver>>,
+}
+
+impl Observers {
+    pub fn add(&mut self, observer: Box<dyn GameObserver>) {
+        self.observers.push(observer);
+    }
+
+    pub fn len(&self) -> usize {
+        self.observers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.observers.is_empty()
+    }
+
+    pub fn notify(&mut self, event: GameEvent) {
+        for observer in self.observers.iter_mut() {
+            observer.on_event(&event);
+        }
+    }
+}
+
+impl fmt::Debug for Observers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Observers({})", self.observers.len())
+    }
+}
+
+/// Keeps every event. Clones share the same log, so one can be registered on
+/// a game while another is read from.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    events: Arc<Mutex<Vec<GameEvent>>>,
+}
+
+impl EventLog {
+    pub fn new() -> Self {
+        EventLog::default()
+    }
+
+    pub fn events(&self) -> Vec<GameEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl GameObserver for EventLog {
+    fn on_event(&mut self, event: &GameEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::GameError;
+    use crate::game::test_support::{seat_players, words};
+    use crate::game::{Game, InitialGame};
+    use crate::players::SimplePlayer;
+    use crate::rules::{RuleSet, StartingTeam};
+
+    fn setup_game(
+        rules: RuleSet,
+        log: &EventLog,
+    ) -> Result<Game<InitialGame, SimplePlayer>, GameError> {
+        let mut game = Game::new(&words(), rules)?;
+        // watch from the start, so the roster events are logged too.
+        game.add_observer(Box::new(log.clone()));
+        seat_players(&mut game, 0, 0)?;
+        Ok(game)
+    }
+
+    #[test]
+    fn observer_roster_events() -> Result<(), GameError> {
+        let log = EventLog::new();
+        let mut game = setup_game(RuleSet::casual(), &log)?;
+        game.add_spectator(SimplePlayer::new("s1", 10))?;
+        game.add_player_team_two(SimplePlayer::new("s1", 10));
+        let events = log.events();
+        assert_eq!(events.len(), 9);
+        assert_eq!(
+            events[0],
+            GameEvent::RosterChanged {
+                player_id: 1,
+                change: RosterChange::Joined(Team::TeamOne)
+            }
+        );
+        assert_eq!(
+            events[5],
+            GameEvent::RosterChanged {
+                player_id: 2,
+                change: RosterChange::BecameSpymaster(Team::TeamTwo)
+            }
+        );
+        assert_eq!(
+            events[6],
+            GameEvent::RosterChanged {
+                player_id: 10,
+                change: RosterChange::Spectating
+            }
+        );
+        assert_eq!(
+            events[7],
+            GameEvent::RosterChanged {
+                player_id: 10,
+                change: RosterChange::LeftSpectators
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn observer_move_events() -> Result<(), GameError> {
+        let log = EventLog::new();
+        let mut rules = RuleSet::classic();
+        rules.starting_team = StartingTeam::TeamOne;
+        let game = setup_game(rules, &log)?;
+        let mut game = game.begin()?;
+        let second_log = EventLog::new();
+        game.add_observer(Box::new(second_log.clone()));
+        let p1 = SimplePlayer::new("p1", 1);
+        let p3 = SimplePlayer::new("p3", 3);
+        let grey_move = (0..25)
+            .find(|x| game.get_board().is_grey_index(*x))
+            .unwrap() as u8;
+
+        game.give_clue(&p1, "clue", 2)?;
+        game.try_unravel(&p3, grey_move)?;
+        let events: Vec<GameEvent> = log.events().into_iter().skip(6).collect();
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::TurnChanged {
+                    team: Team::TeamOne
+                },
+                GameEvent::ClueGiven(Clue {
+                    team: Team::TeamOne,
+                    word: String::from("clue"),
+                    number: 2
+                }),
+                GameEvent::TileUnraveled {
+                    player_id: 3,
+                    team: Team::TeamOne,
+                    tile_id: grey_move,
+                    kind: TileKind::Grey
+                },
+                GameEvent::TurnChanged {
+                    team: Team::TeamTwo
+                },
+            ]
+        );
+        // both observers see the events after registration.
+        assert_eq!(second_log.events(), events[1..].to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn observer_win_event() -> Result<(), GameError> {
+        let log = EventLog::new();
+        let mut game = setup_game(RuleSet::casual(), &log)?.begin()?;
+        let p3 = SimplePlayer::new("p3", 3);
+        game.try_unravel(&p3, game.get_board().danger_index())?;
+        assert_eq!(
+            log.events().last(),
+            Some(&GameEvent::GameWon {
+                team: Team::TeamTwo,
+                reason: WinReason::OpponentDangerDraw
+            })
+        );
+        Ok(())
+    }
+}
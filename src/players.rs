@@ -1,9 +1,95 @@
+use crate::errors::GameError;
+use crate::i18n::Locale;
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+
+pub const MAX_DISPLAY_NAME_LEN: usize = 24;
+
+/// A player name that is safe to show: trimmed, 1 to 24 characters and free
+/// of control characters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DisplayName(String);
+
+impl DisplayName {
+    pub fn new(name: &str) -> Result<Self, GameError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(GameError::InvalidDisplayName);
+        }
+        if name.chars().count() > MAX_DISPLAY_NAME_LEN {
+            return Err(GameError::InvalidDisplayName);
+        }
+        if name.chars().any(char::is_control) {
+            return Err(GameError::InvalidDisplayName);
+        }
+        Ok(DisplayName(String::from(name)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0[..]
+    }
+}
+
+impl TryFrom<String> for DisplayName {
+    type Error = GameError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        DisplayName::new(&value)
+    }
+}
+
+impl From<DisplayName> for String {
+    fn from(name: DisplayName) -> Self {
+        name.0
+    }
+}
+
+impl fmt::Display for DisplayName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// How a player shows up next to their name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Avatar {
+    /// A `#rrggbb` colour.
+    Color(String),
+    Image(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Presence {
+    Online,
+    Away,
+    Offline,
+}
+
 pub trait Player {
     fn get_name(&self) -> &str;
     fn get_id(&self) -> &u32;
+
+    fn get_avatar(&self) -> Option<&Avatar> {
+        None
+    }
+
+    fn get_pronouns(&self) -> Option<&str> {
+        None
+    }
+
+    /// The language the player picked, if any.
+    fn get_locale(&self) -> Option<Locale> {
+        None
+    }
+
+    fn get_presence(&self) -> Presence {
+        Presence::Online
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct SimplePlayer {
     name: String,
     id: u32,
@@ -26,3 +112,135 @@ impl Player for SimplePlayer {
         &self.id
     }
 }
+
+/// A player with all the display metadata filled in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerProfile {
+    id: u32,
+    name: DisplayName,
+    #[serde(default)]
+    avatar: Option<Avatar>,
+    #[serde(default)]
+    pronouns: Option<String>,
+    #[serde(default)]
+    locale: Option<Locale>,
+    /// Profiles saved without a presence come back offline.
+    #[serde(default = "default_presence")]
+    presence: Presence,
+}
+
+fn default_presence() -> Presence {
+    Presence::Offline
+}
+
+impl PlayerProfile {
+    pub fn new(name: DisplayName, id: u32) -> Self {
+        PlayerProfile {
+            id,
+            name,
+            avatar: None,
+            pronouns: None,
+            locale: None,
+            presence: Presence::Online,
+        }
+    }
+
+    pub fn set_name(&mut self, name: DisplayName) {
+        self.name = name;
+    }
+
+    pub fn set_avatar(&mut self, avatar: Option<Avatar>) -> Result<(), GameError> {
+        if let Some(Avatar::Color(color)) = &avatar {
+            let is_hex = color.len() == 7
+                && color.starts_with('#')
+                && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+            if !is_hex {
+                return Err(GameError::InvalidAvatar);
+            }
+        }
+        self.avatar = avatar;
+        Ok(())
+    }
+
+    pub fn set_pronouns(&mut self, pronouns: Option<&str>) {
+        self.pronouns = pronouns
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .map(String::from);
+    }
+
+    pub fn set_locale(&mut self, locale: Option<Locale>) {
+        self.locale = locale;
+    }
+
+    pub fn set_presence(&mut self, presence: Presence) {
+        self.presence = presence;
+    }
+}
+
+impl Player for PlayerProfile {
+    fn get_name(&self) -> &str {
+        self.name.as_str()
+    }
+    fn get_id(&self) -> &u32 {
+        &self.id
+    }
+    fn get_avatar(&self) -> Option<&Avatar> {
+        self.avatar.as_ref()
+    }
+    fn get_pronouns(&self) -> Option<&str> {
+        self.pronouns.as_deref()
+    }
+    fn get_locale(&self) -> Option<Locale> {
+        self.locale
+    }
+    fn get_presence(&self) -> Presence {
+        self.presence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn players_display_name_validation() {
+        assert_eq!(DisplayName::new("  Ada ").unwrap().as_str(), "Ada");
+        assert!(DisplayName::new("   ").is_err());
+        assert!(DisplayName::new("bad\nname").is_err());
+        assert!(DisplayName::new(&"x".repeat(MAX_DISPLAY_NAME_LEN)).is_ok());
+        assert!(DisplayName::new(&"x".repeat(MAX_DISPLAY_NAME_LEN + 1)).is_err());
+        // deserializing validates too.
+        assert!(serde_json::from_str::<DisplayName>("\"\"").is_err());
+    }
+
+    #[test]
+    fn players_default_metadata() {
+        let player = SimplePlayer::new("p1", 1);
+        assert_eq!(player.get_avatar(), None);
+        assert_eq!(player.get_pronouns(), None);
+        assert_eq!(player.get_locale(), None);
+        assert_eq!(player.get_presence(), Presence::Online);
+    }
+
+    #[test]
+    fn players_profile_metadata() -> Result<(), GameError> {
+        let mut player = PlayerProfile::new(DisplayName::new("Ada")?, 7);
+        player.set_avatar(Some(Avatar::Color(String::from("#1a2B3c"))))?;
+        assert!(player
+            .set_avatar(Some(Avatar::Color(String::from("red"))))
+            .is_err());
+        player.set_pronouns(Some(" she/her "));
+        player.set_locale(Some(Locale::De));
+        player.set_presence(Presence::Away);
+        assert_eq!(player.get_name(), "Ada");
+        assert_eq!(player.get_pronouns(), Some("she/her"));
+        assert_eq!(
+            player.get_avatar(),
+            Some(&Avatar::Color(String::from("#1a2B3c")))
+        );
+        assert_eq!(player.get_locale(), Some(Locale::De));
+        assert_eq!(player.get_presence(), Presence::Away);
+        Ok(())
+    }
+}
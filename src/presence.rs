@@ -0,0 +1,200 @@
+use crate::clock::Clock;
+use crate::players::Presence;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A player is online while their heartbeats keep coming. After missing them
+/// for `heartbeat_timeout` they are away but keep their seat, and once
+/// `grace_period` more has passed without a heartbeat they are gone.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresenceConfig {
+    pub heartbeat_timeout: Duration,
+    pub grace_period: Duration,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        PresenceConfig {
+            heartbeat_timeout: Duration::from_secs(20),
+            grace_period: Duration::from_secs(120),
+        }
+    }
+}
+
+/// What changed since the last `sweep`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PresenceUpdate {
+    pub changed: Vec<(u32, Presence)>,
+    /// Players whose grace period ran out. They are no longer tracked.
+    pub expired: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Tracked {
+    last_seen: Duration,
+    /// Set when the client said goodbye, e.g. on tab close, so the player
+    /// is away right away instead of after the heartbeat timeout.
+    disconnected: bool,
+    reported: Presence,
+}
+
+/// Heartbeats of the players in one lobby.
+#[derive(Debug)]
+pub struct PresenceTracker {
+    config: PresenceConfig,
+    clock: Arc<dyn Clock>,
+    players: BTreeMap<u32, Tracked>,
+}
+
+impl PresenceTracker {
+    pub fn new(config: PresenceConfig, clock: Arc<dyn Clock>) -> Self {
+        PresenceTracker {
+            config,
+            clock,
+            players: BTreeMap::new(),
+        }
+    }
+
+    /// Note a sign of life. Returns true if the player was away, i.e. this is
+    /// a reconnect.
+    pub fn heartbeat(&mut self, player_id: u32) -> bool {
+        let now = self.clock.now();
+        let reconnected = self.status(player_id) == Presence::Away;
+        let tracked = self.players.entry(player_id).or_insert(Tracked {
+            last_seen: now,
+            disconnected: false,
+            reported: Presence::Offline,
+        });
+        tracked.last_seen = now;
+        tracked.disconnected = false;
+        reconnected
+    }
+
+    /// The player's connection closed. Their seat is held for the grace
+    /// period starting now.
+    pub fn disconnect(&mut self, player_id: u32) {
+        let now = self.clock.now();
+        if let Some(tracked) = self.players.get_mut(&player_id) {
+            tracked.last_seen = now;
+            tracked.disconnected = true;
+        }
+    }
+
+    /// Stop tracking someone who left for good.
+    pub fn forget(&mut self, player_id: u32) {
+        self.players.remove(&player_id);
+    }
+
+    pub fn status(&self, player_id: u32) -> Presence {
+        match self.players.get(&player_id) {
+            Some(tracked) => self.status_of(tracked),
+            None => Presence::Offline,
+        }
+    }
+
+    fn status_of(&self, tracked: &Tracked) -> Presence {
+        let silent = self.clock.now() - tracked.last_seen;
+        let timeout = if tracked.disconnected {
+            Duration::default()
+        } else {
+            self.config.heartbeat_timeout
+        };
+        if silent < timeout {
+            Presence::Online
+        } else if silent < timeout + self.config.grace_period {
+            Presence::Away
+        } else {
+            Presence::Offline
+        }
+    }
+
+    /// Ids of everyone currently online.
+    pub fn connected(&self) -> Vec<u32> {
+        self.players
+            .iter()
+            .filter(|(_, t)| self.status_of(t) == Presence::Online)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Work out who came, went away or ran out of grace since last time.
+    pub fn sweep(&mut self) -> PresenceUpdate {
+        let mut update = PresenceUpdate::default();
+        let statuses: Vec<(u32, Presence)> = self
+            .players
+            .iter()
+            .map(|(id, t)| (*id, self.status_of(t)))
+            .collect();
+        for (id, status) in statuses {
+            let tracked = self.players.get_mut(&id).unwrap();
+            if tracked.reported != status {
+                tracked.reported = status;
+                update.changed.push((id, status));
+            }
+            if status == Presence::Offline {
+                self.players.remove(&id);
+                update.expired.push(id);
+            }
+        }
+        update
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+
+    fn tracker(clock: &Arc<ManualClock>) -> PresenceTracker {
+        PresenceTracker::new(PresenceConfig::default(), clock.clone())
+    }
+
+    #[test]
+    fn presence_heartbeats_keep_players_online() {
+        let clock = Arc::new(ManualClock::new());
+        let mut presence = tracker(&clock);
+        assert_eq!(presence.status(1), Presence::Offline);
+        assert!(!presence.heartbeat(1));
+        presence.heartbeat(2);
+        assert_eq!(
+            presence.sweep().changed,
+            vec![(1, Presence::Online), (2, Presence::Online)]
+        );
+        clock.advance(Duration::from_secs(15));
+        presence.heartbeat(1);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(presence.connected(), vec![1]);
+        assert_eq!(presence.status(2), Presence::Away);
+        assert_eq!(presence.sweep().changed, vec![(2, Presence::Away)]);
+        // nothing new to report.
+        assert_eq!(presence.sweep(), PresenceUpdate::default());
+    }
+
+    #[test]
+    fn presence_reconnect_within_grace_period() {
+        let clock = Arc::new(ManualClock::new());
+        let mut presence = tracker(&clock);
+        presence.heartbeat(1);
+        presence.disconnect(1);
+        assert_eq!(presence.status(1), Presence::Away);
+        clock.advance(Duration::from_secs(119));
+        assert!(presence.heartbeat(1));
+        assert_eq!(presence.status(1), Presence::Online);
+        assert!(presence.sweep().expired.is_empty());
+    }
+
+    #[test]
+    fn presence_expires_after_grace_period() {
+        let clock = Arc::new(ManualClock::new());
+        let mut presence = tracker(&clock);
+        presence.heartbeat(1);
+        presence.sweep();
+        clock.advance(Duration::from_secs(140));
+        let update = presence.sweep();
+        assert_eq!(update.changed, vec![(1, Presence::Offline)]);
+        assert_eq!(update.expired, vec![1]);
+        // a late heartbeat is a fresh start, not a reconnect.
+        assert!(!presence.heartbeat(1));
+    }
+}
@@ -0,0 +1,297 @@
+use crate::game::{GameResult, Team};
+use serde::{Deserialize, Serialize};
+
+pub const INITIAL_RATING: f64 = 1500.0;
+/// How far a single game can move a rating.
+const K_FACTOR: f64 = 32.0;
+/// Players in their first games move faster so they find their level sooner.
+const PROVISIONAL_K_FACTOR: f64 = 64.0;
+const PROVISIONAL_GAMES: u32 = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Role {
+    Guesser,
+    Spymaster,
+}
+
+/// An Elo rating and the number of games behind it.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub value: f64,
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Self {
+        Rating {
+            value: INITIAL_RATING,
+            games: 0,
+        }
+    }
+}
+
+impl Rating {
+    fn k_factor(&self) -> f64 {
+        if self.games < PROVISIONAL_GAMES {
+            PROVISIONAL_K_FACTOR
+        } else {
+            K_FACTOR
+        }
+    }
+}
+
+/// Guessing and giving clues are different skills, so each gets a rating.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PlayerRatings {
+    pub guesser: Rating,
+    pub spymaster: Rating,
+}
+
+impl PlayerRatings {
+    pub fn get(&self, role: Role) -> Rating {
+        match role {
+            Role::Guesser => self.guesser,
+            Role::Spymaster => self.spymaster,
+        }
+    }
+
+    fn get_mut(&mut self, role: Role) -> &mut Rating {
+        match role {
+            Role::Guesser => &mut self.guesser,
+            Role::Spymaster => &mut self.spymaster,
+        }
+    }
+
+    pub fn apply(&mut self, change: &RatingChange) {
+        *self.get_mut(change.role) = change.after;
+    }
+}
+
+/// One rating that moved because of a finished game.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RatingChange {
+    pub player_id: u32,
+    pub role: Role,
+    pub before: Rating,
+    pub after: Rating,
+}
+
+/// A team's strength: the spymaster's rating and the guessers' average count
+/// half each. A team missing either side is rated on the other alone.
+pub fn team_rating<F>(spymaster: Option<u32>, players: &[u32], ratings: F) -> f64
+where
+    F: Fn(u32) -> PlayerRatings,
+{
+    let guessers: Vec<f64> = players
+        .iter()
+        .filter(|id| Some(**id) != spymaster)
+        .map(|id| ratings(*id).guesser.value)
+        .collect();
+    let guessers = if guessers.is_empty() {
+        None
+    } else {
+        Some(guessers.iter().sum::<f64>() / guessers.len() as f64)
+    };
+    let spymaster = spymaster.map(|id| ratings(id).spymaster.value);
+    match (spymaster, guessers) {
+        (Some(s), Some(g)) => (s + g) / 2.0,
+        (Some(s), None) => s,
+        (None, Some(g)) => g,
+        (None, None) => INITIAL_RATING,
+    }
+}
+
+/// Chance that a team rated `rating` beats one rated `opponent`.
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+/// New ratings for everyone in a finished game. Every player moves by their
+/// team's surprise, scaled by their own K factor, in the role they played.
+pub fn rate_game<F>(result: &GameResult, ratings: F) -> Vec<RatingChange>
+where
+    F: Fn(u32) -> PlayerRatings,
+{
+    let team_one = team_rating(
+        result.team_one_spymaster,
+        &result.team_one_players,
+        &ratings,
+    );
+    let team_two = team_rating(
+        result.team_two_spymaster,
+        &result.team_two_players,
+        &ratings,
+    );
+    let teams = [
+        (
+            Team::TeamOne,
+            &result.team_one_players,
+            result.team_one_spymaster,
+            expected_score(team_one, team_two),
+        ),
+        (
+            Team::TeamTwo,
+            &result.team_two_players,
+            result.team_two_spymaster,
+            expected_score(team_two, team_one),
+        ),
+    ];
+    let mut changes = Vec::new();
+    for (team, players, spymaster, expected) in teams.iter() {
+        let score = if result.winner == *team { 1.0 } else { 0.0 };
+        for id in players.iter() {
+            let role = if *spymaster == Some(*id) {
+                Role::Spymaster
+            } else {
+                Role::Guesser
+            };
+            let before = ratings(*id).get(role);
+            let after = Rating {
+                value: before.value + before.k_factor() * (score - expected),
+                games: before.games + 1,
+            };
+            changes.push(RatingChange {
+                player_id: *id,
+                role,
+                before,
+                after,
+            });
+        }
+    }
+    changes
+}
+
+/// One row of a leaderboard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub player_id: u32,
+    pub name: String,
+    pub rating: f64,
+    pub games: u32,
+}
+
+/// Rank `(id, name, ratings)` rows by their rating in `role`, best first.
+/// Players who never played the role are left out.
+pub fn leaderboard<'a, I>(players: I, role: Role, limit: usize) -> Vec<LeaderboardEntry>
+where
+    I: IntoIterator<Item = (u32, &'a str, PlayerRatings)>,
+{
+    let mut rows: Vec<(u32, &str, Rating)> = players
+        .into_iter()
+        .map(|(id, name, ratings)| (id, name, ratings.get(role)))
+        .filter(|(_, _, rating)| rating.games > 0)
+        .collect();
+    rows.sort_by(|a, b| b.2.value.total_cmp(&a.2.value).then(a.0.cmp(&b.0)));
+    rows.into_iter()
+        .take(limit)
+        .enumerate()
+        .map(|(i, (id, name, rating))| LeaderboardEntry {
+            rank: i + 1,
+            player_id: id,
+            name: String::from(name),
+            rating: rating.value,
+            games: rating.games,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::WinReason;
+    use std::collections::BTreeMap;
+
+    fn result(winner: Team) -> GameResult {
+        GameResult {
+            winner,
+            reason: WinReason::ScoreReached,
+            starting_team: Team::TeamOne,
+            team_one_score: 9,
+            team_two_score: 4,
+            team_one_players: vec![1, 2],
+            team_two_players: vec![3, 4],
+            team_one_spymaster: Some(1),
+            team_two_spymaster: Some(3),
+            player_stats: BTreeMap::new(),
+            handicap: None,
+        }
+    }
+
+    #[test]
+    fn ratings_winners_gain_losers_lose() {
+        let changes = rate_game(&result(Team::TeamOne), |_| PlayerRatings::default());
+        assert_eq!(changes.len(), 4);
+        for change in changes.iter() {
+            let won = change.player_id <= 2;
+            assert_eq!(change.after.value > change.before.value, won);
+            assert_eq!(change.after.games, 1);
+            let role = if change.player_id % 2 == 1 {
+                Role::Spymaster
+            } else {
+                Role::Guesser
+            };
+            assert_eq!(change.role, role);
+        }
+        // evenly matched newcomers move by half the provisional factor.
+        assert_eq!(changes[0].after.value, INITIAL_RATING + 32.0);
+        assert_eq!(changes[2].after.value, INITIAL_RATING - 32.0);
+    }
+
+    #[test]
+    fn ratings_upsets_move_more() {
+        let strong = |id: u32| {
+            let value = if id <= 2 { 1800.0 } else { 1400.0 };
+            let rating = Rating { value, games: 50 };
+            PlayerRatings {
+                guesser: rating,
+                spymaster: rating,
+            }
+        };
+        let expected_win = rate_game(&result(Team::TeamOne), strong);
+        let upset = rate_game(&result(Team::TeamTwo), strong);
+        let gain = |changes: &[RatingChange], id: u32| {
+            let c = changes.iter().find(|c| c.player_id == id).unwrap();
+            c.after.value - c.before.value
+        };
+        assert!(gain(&upset, 3) > gain(&expected_win, 1));
+        assert!(gain(&expected_win, 1) < 5.0);
+    }
+
+    #[test]
+    fn ratings_team_rating() {
+        let ratings = |id: u32| {
+            let mut r = PlayerRatings::default();
+            r.spymaster.value = 1600.0;
+            r.guesser.value = 1400.0 + id as f64 * 100.0;
+            r
+        };
+        // spymaster 1600, guessers 1600 and 1700.
+        assert_eq!(team_rating(Some(1), &[1, 2, 3], ratings), 1625.0);
+        assert_eq!(team_rating(None, &[2], ratings), 1600.0);
+        assert_eq!(team_rating(None, &[], ratings), INITIAL_RATING);
+    }
+
+    #[test]
+    fn ratings_leaderboard() {
+        let rated = |guesser: f64, games: u32| PlayerRatings {
+            guesser: Rating {
+                value: guesser,
+                games,
+            },
+            spymaster: Rating::default(),
+        };
+        let rows = vec![
+            (1, "Ada", rated(1550.0, 3)),
+            (2, "Bob", rated(1600.0, 1)),
+            (3, "Carol", rated(1700.0, 0)),
+            (4, "Dan", rated(1550.0, 2)),
+        ];
+        let board = leaderboard(rows.clone(), Role::Guesser, 10);
+        let names: Vec<&str> = board.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Bob", "Ada", "Dan"]);
+        assert_eq!(board[2].rank, 3);
+        assert_eq!(leaderboard(rows.clone(), Role::Guesser, 1).len(), 1);
+        assert!(leaderboard(rows, Role::Spymaster, 10).is_empty());
+    }
+}
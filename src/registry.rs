@@ -0,0 +1,341 @@
+use crate::errors::GameError;
+use crate::game::{GameResult, Team};
+use crate::i18n::Locale;
+use crate::players::{Avatar, DisplayName, Player, PlayerProfile, Presence};
+use crate::ratings::{self, LeaderboardEntry, PlayerRatings, RatingChange, Role};
+use crate::stats::PlayerStats;
+use crate::storage::{storage_error, write_atomically};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const REGISTRY_VERSION: u32 = 1;
+
+/// One finished game in a player's history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayedGame {
+    pub lobby_id: String,
+    pub team: Team,
+    pub won: bool,
+    pub was_spymaster: bool,
+    pub stats: PlayerStats,
+}
+
+/// A player with a stable id that outlives lobbies.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegisteredPlayer {
+    profile: PlayerProfile,
+    history: Vec<PlayedGame>,
+    ratings: PlayerRatings,
+}
+
+impl RegisteredPlayer {
+    pub fn profile(&self) -> &PlayerProfile {
+        &self.profile
+    }
+
+    pub fn profile_mut(&mut self) -> &mut PlayerProfile {
+        &mut self.profile
+    }
+
+    pub fn history(&self) -> &[PlayedGame] {
+        &self.history
+    }
+
+    pub fn ratings(&self) -> &PlayerRatings {
+        &self.ratings
+    }
+
+    pub fn games_won(&self) -> usize {
+        self.history.iter().filter(|g| g.won).count()
+    }
+}
+
+impl Player for RegisteredPlayer {
+    fn get_name(&self) -> &str {
+        self.profile.get_name()
+    }
+    fn get_id(&self) -> &u32 {
+        self.profile.get_id()
+    }
+    fn get_avatar(&self) -> Option<&Avatar> {
+        self.profile.get_avatar()
+    }
+    fn get_pronouns(&self) -> Option<&str> {
+        self.profile.get_pronouns()
+    }
+    fn get_locale(&self) -> Option<Locale> {
+        self.profile.get_locale()
+    }
+    fn get_presence(&self) -> Presence {
+        self.profile.get_presence()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RegistryFile {
+    version: u32,
+    next_id: u32,
+    players: Vec<RegisteredPlayer>,
+}
+
+/// Every registered player, optionally backed by a JSON file that is
+/// rewritten after each change.
+#[derive(Debug)]
+pub struct PlayerRegistry {
+    path: Option<PathBuf>,
+    players: BTreeMap<u32, RegisteredPlayer>,
+    next_id: u32,
+}
+
+impl PlayerRegistry {
+    /// A registry that forgets everything when dropped.
+    pub fn in_memory() -> Self {
+        PlayerRegistry {
+            path: None,
+            players: BTreeMap::new(),
+            next_id: 1,
+        }
+    }
+
+    /// Load the registry at `path`, or start an empty one there if the file
+    /// doesn't exist yet.
+    pub fn open<T: AsRef<Path>>(path: T) -> Result<Self, GameError> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            let mut registry = PlayerRegistry::in_memory();
+            registry.path = Some(path);
+            return Ok(registry);
+        }
+        let data = fs::read_to_string(&path).map_err(storage_error)?;
+        let file: RegistryFile = serde_json::from_str(&data).map_err(storage_error)?;
+        if file.version == 0 || file.version > REGISTRY_VERSION {
+            return Err(GameError::UnsupportedRegistryVersion(file.version));
+        }
+        let players = file.players.into_iter().map(|p| (*p.get_id(), p)).collect();
+        Ok(PlayerRegistry {
+            path: Some(path),
+            players,
+            next_id: file.next_id,
+        })
+    }
+
+    /// Write the registry to its file. Does nothing for in-memory registries.
+    pub fn save(&self) -> Result<(), GameError> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let file = RegistryFile {
+            version: REGISTRY_VERSION,
+            next_id: self.next_id,
+            players: self.players.values().cloned().collect(),
+        };
+        let data = serde_json::to_string_pretty(&file).map_err(storage_error)?;
+        write_atomically(path, &data)
+    }
+
+    pub fn register(&mut self, name: DisplayName) -> Result<&RegisteredPlayer, GameError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut profile = PlayerProfile::new(name, id);
+        profile.set_presence(Presence::Offline);
+        self.players.insert(
+            id,
+            RegisteredPlayer {
+                profile,
+                history: Vec::new(),
+                ratings: PlayerRatings::default(),
+            },
+        );
+        self.save()?;
+        Ok(&self.players[&id])
+    }
+
+    pub fn get(&self, id: u32) -> Option<&RegisteredPlayer> {
+        self.players.get(&id)
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &RegisteredPlayer> {
+        self.players.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// Change a profile and save.
+    pub fn update<F>(&mut self, id: u32, change: F) -> Result<(), GameError>
+    where
+        F: FnOnce(&mut PlayerProfile) -> Result<(), GameError>,
+    {
+        let player = self
+            .players
+            .get_mut(&id)
+            .ok_or(GameError::UnknownPlayer(id))?;
+        change(&mut player.profile)?;
+        self.save()
+    }
+
+    /// Add a finished game to the history of every registered player in it
+    /// and update their ratings. Players who aren't registered count at the
+    /// initial rating.
+    pub fn record_game(
+        &mut self,
+        lobby_id: &str,
+        result: &GameResult,
+    ) -> Result<Vec<RatingChange>, GameError> {
+        let changes: Vec<RatingChange> = ratings::rate_game(result, |id| {
+            self.players.get(&id).map(|p| p.ratings).unwrap_or_default()
+        })
+        .into_iter()
+        .filter(|c| self.players.contains_key(&c.player_id))
+        .collect();
+        for change in changes.iter() {
+            if let Some(player) = self.players.get_mut(&change.player_id) {
+                player.ratings.apply(change);
+            }
+        }
+        let teams = [
+            (
+                Team::TeamOne,
+                &result.team_one_players,
+                result.team_one_spymaster,
+            ),
+            (
+                Team::TeamTwo,
+                &result.team_two_players,
+                result.team_two_spymaster,
+            ),
+        ];
+        for (team, ids, spymaster) in teams.iter() {
+            for id in ids.iter() {
+                if let Some(player) = self.players.get_mut(id) {
+                    player.history.push(PlayedGame {
+                        lobby_id: String::from(lobby_id),
+                        team: *team,
+                        won: result.winner == *team,
+                        was_spymaster: *spymaster == Some(*id),
+                        stats: result.player_stats.get(id).cloned().unwrap_or_default(),
+                    });
+                }
+            }
+        }
+        self.save()?;
+        Ok(changes)
+    }
+
+    /// The best `limit` registered players in `role`.
+    pub fn leaderboard(&self, role: Role, limit: usize) -> Vec<LeaderboardEntry> {
+        ratings::leaderboard(
+            self.players
+                .values()
+                .map(|p| (*p.get_id(), p.get_name(), p.ratings)),
+            role,
+            limit,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::WinReason;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn temp_path(name: &str) -> PathBuf {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        std::env::temp_dir().join(format!("codeword-{}-{}.json", name, nanos))
+    }
+
+    fn result() -> GameResult {
+        GameResult {
+            winner: Team::TeamTwo,
+            reason: WinReason::ScoreReached,
+            starting_team: Team::TeamOne,
+            team_one_score: 3,
+            team_two_score: 0,
+            team_one_players: vec![1],
+            team_two_players: vec![2, 99],
+            team_one_spymaster: Some(1),
+            team_two_spymaster: Some(2),
+            player_stats: BTreeMap::new(),
+            handicap: None,
+        }
+    }
+
+    #[test]
+    fn registry_register_and_update() -> Result<(), GameError> {
+        let mut registry = PlayerRegistry::in_memory();
+        let ada = *registry.register(DisplayName::new("Ada")?)?.get_id();
+        let bob = *registry.register(DisplayName::new("Bob")?)?.get_id();
+        assert_ne!(ada, bob);
+        registry.update(ada, |p| {
+            p.set_locale(Some(Locale::Es));
+            Ok(())
+        })?;
+        assert_eq!(registry.get(ada).unwrap().get_locale(), Some(Locale::Es));
+        assert_eq!(
+            registry.update(42, |_| Ok(())),
+            Err(GameError::UnknownPlayer(42))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn registry_survives_restart() -> Result<(), GameError> {
+        let path = temp_path("registry");
+        {
+            let mut registry = PlayerRegistry::open(&path)?;
+            registry.register(DisplayName::new("Ada")?)?;
+            registry.register(DisplayName::new("Bob")?)?;
+            registry.update(1, |p| {
+                p.set_pronouns(Some("she/her"));
+                Ok(())
+            })?;
+            registry.record_game("lobby-1", &result())?;
+        }
+        let mut registry = PlayerRegistry::open(&path)?;
+        assert_eq!(registry.len(), 2);
+        let ada = registry.get(1).unwrap();
+        assert_eq!(ada.get_name(), "Ada");
+        assert_eq!(ada.get_pronouns(), Some("she/her"));
+        assert_eq!(ada.history().len(), 1);
+        assert!(!ada.history()[0].won);
+        assert!(ada.history()[0].was_spymaster);
+        assert_eq!(registry.get(2).unwrap().games_won(), 1);
+        assert!(registry.get(2).unwrap().ratings().spymaster.value > 1500.0);
+        assert_eq!(registry.get(1).unwrap().ratings().spymaster.games, 1);
+        let board = registry.leaderboard(Role::Spymaster, 10);
+        assert_eq!(board[0].player_id, 2);
+        // ids keep counting after a restart.
+        let carol = *registry.register(DisplayName::new("Carol")?)?.get_id();
+        assert_eq!(carol, 3);
+        fs::remove_file(&path).ok();
+        Ok(())
+    }
+
+    #[test]
+    fn registry_rejects_bad_files() {
+        let path = temp_path("broken");
+        fs::write(&path, "not json").unwrap();
+        match PlayerRegistry::open(&path) {
+            Err(GameError::Storage(_)) => {}
+            other => panic!("expected a storage error, got {:?}", other),
+        }
+        fs::write(&path, r#"{"version": 9, "next_id": 1, "players": []}"#).unwrap();
+        assert_eq!(
+            PlayerRegistry::open(&path).map(|_| ()),
+            Err(GameError::UnsupportedRegistryVersion(9))
+        );
+        fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,214 @@
+use crate::errors::GameError;
+use crate::game::Team;
+use rand::{thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StartingTeam {
+    TeamOne,
+    TeamTwo,
+    Random,
+}
+
+impl StartingTeam {
+    pub fn pick(&self) -> Team {
+        match self {
+            StartingTeam::TeamOne => Team::TeamOne,
+            StartingTeam::TeamTwo => Team::TeamTwo,
+            StartingTeam::Random => {
+                if thread_rng().gen::<bool>() {
+                    Team::TeamOne
+                } else {
+                    Team::TeamTwo
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GuessLimit {
+    /// Guess until a wrong tile is hit. Clues are optional.
+    Unlimited,
+    /// A clue is required before guessing and allows its number plus one guesses.
+    ClueNumberPlusOne,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AssassinRule {
+    LoseGame,
+    EndTurn,
+}
+
+/// Evens out a mismatch: the handicapped team has fewer cards to find and
+/// more time on its clocks.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Handicap {
+    pub team: Team,
+    pub fewer_cards: u8,
+    /// Added to the team clock and to each of the team's turns.
+    pub extra_time: Duration,
+}
+
+/// Everything that decides how a game is set up and played.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RuleSet {
+    /// Players needed on each team, spymaster included.
+    pub min_team_size: usize,
+    pub starting_team_cards: u8,
+    pub other_team_cards: u8,
+    pub starting_team: StartingTeam,
+    pub guess_limit: GuessLimit,
+    pub assassin: AssassinRule,
+    pub handicap: Option<Handicap>,
+}
+
+impl RuleSet {
+    /// The board game rules: 9 cards for the team going first, 8 for the other.
+    pub fn classic() -> Self {
+        RuleSet {
+            min_team_size: 2,
+            starting_team_cards: 9,
+            other_team_cards: 8,
+            starting_team: StartingTeam::Random,
+            guess_limit: GuessLimit::ClueNumberPlusOne,
+            assassin: AssassinRule::LoseGame,
+            handicap: None,
+        }
+    }
+
+    /// Even 8/8 split, team one always starts and guessing is unlimited.
+    pub fn casual() -> Self {
+        RuleSet {
+            min_team_size: 2,
+            starting_team_cards: 8,
+            other_team_cards: 8,
+            starting_team: StartingTeam::TeamOne,
+            guess_limit: GuessLimit::Unlimited,
+            assassin: AssassinRule::LoseGame,
+            handicap: None,
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "classic" => Some(RuleSet::classic()),
+            "casual" => Some(RuleSet::casual()),
+            _ => None,
+        }
+    }
+
+    pub fn validate(&self, num_words: usize) -> Result<(), GameError> {
+        if self.min_team_size < 2 {
+            return Err(GameError::TeamTooSmall);
+        }
+        if self.starting_team_cards == 0 || self.other_team_cards == 0 {
+            return Err(GameError::TeamWithoutCards);
+        }
+        // one more for the danger tile.
+        let needed = self.starting_team_cards as usize + self.other_team_cards as usize + 1;
+        if needed > num_words {
+            return Err(GameError::CardsDontFit);
+        }
+        if let Some(handicap) = self.handicap {
+            let fewest = self.starting_team_cards.min(self.other_team_cards);
+            if handicap.fewer_cards >= fewest {
+                return Err(GameError::HandicapTooLarge);
+            }
+        }
+        Ok(())
+    }
+
+    /// Card counts for (team one, team two) when `starting` goes first.
+    /// The handicap, if any, is already taken off.
+    pub fn card_counts(&self, starting: Team) -> (u8, u8) {
+        let (mut team_one, mut team_two) = match starting {
+            Team::TeamOne => (self.starting_team_cards, self.other_team_cards),
+            Team::TeamTwo => (self.other_team_cards, self.starting_team_cards),
+        };
+        if let Some(handicap) = self.handicap {
+            match handicap.team {
+                Team::TeamOne => team_one = team_one.saturating_sub(handicap.fewer_cards),
+                Team::TeamTwo => team_two = team_two.saturating_sub(handicap.fewer_cards),
+            }
+        }
+        (team_one, team_two)
+    }
+
+    /// Time the handicap adds to `team`'s clocks.
+    pub fn extra_time(&self, team: Team) -> Duration {
+        match self.handicap {
+            Some(handicap) if handicap.team == team => handicap.extra_time,
+            _ => Duration::default(),
+        }
+    }
+}
+
+/// The casual rules, which is how games played before rule sets existed.
+impl Default for RuleSet {
+    fn default() -> Self {
+        RuleSet::casual()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_presets_are_valid() {
+        for name in ["classic", "casual"].iter() {
+            let rules = RuleSet::preset(name).unwrap();
+            assert!(rules.validate(25).is_ok(), "{} preset is invalid", name);
+        }
+        assert!(RuleSet::preset("unknown").is_none());
+    }
+
+    #[test]
+    fn rules_validation() {
+        let mut rules = RuleSet::classic();
+        rules.min_team_size = 1;
+        assert!(rules.validate(25).is_err());
+
+        let mut rules = RuleSet::classic();
+        rules.other_team_cards = 0;
+        assert!(rules.validate(25).is_err());
+
+        let mut rules = RuleSet::classic();
+        rules.starting_team_cards = 12;
+        rules.other_team_cards = 12;
+        assert!(rules.validate(25).is_ok());
+        rules.other_team_cards = 13;
+        assert!(rules.validate(25).is_err());
+    }
+
+    #[test]
+    fn rules_card_counts() {
+        let rules = RuleSet::classic();
+        assert_eq!(rules.card_counts(Team::TeamOne), (9, 8));
+        assert_eq!(rules.card_counts(Team::TeamTwo), (8, 9));
+    }
+
+    #[test]
+    fn rules_handicap() {
+        let mut rules = RuleSet::classic();
+        rules.handicap = Some(Handicap {
+            team: Team::TeamTwo,
+            fewer_cards: 2,
+            extra_time: Duration::from_secs(30),
+        });
+        assert!(rules.validate(25).is_ok());
+        assert_eq!(rules.card_counts(Team::TeamOne), (9, 6));
+        assert_eq!(rules.card_counts(Team::TeamTwo), (8, 7));
+        assert_eq!(rules.extra_time(Team::TeamOne), Duration::default());
+        assert_eq!(rules.extra_time(Team::TeamTwo), Duration::from_secs(30));
+
+        rules.handicap = Some(Handicap {
+            team: Team::TeamOne,
+            fewer_cards: 8,
+            extra_time: Duration::default(),
+        });
+        assert!(rules.validate(25).is_err());
+    }
+}
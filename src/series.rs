@@ -0,0 +1,169 @@
+use crate::errors::GameError;
+use crate::game::{GameResult, Team, WinReason};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SeriesFormat {
+    /// Best of an odd number of rounds, e.g. best-of-3.
+    BestOf(u8),
+    /// First team to win this many rounds.
+    FirstTo(u8),
+}
+
+impl SeriesFormat {
+    pub fn wins_needed(&self) -> u8 {
+        match self {
+            SeriesFormat::BestOf(rounds) => rounds / 2 + 1,
+            SeriesFormat::FirstTo(wins) => *wins,
+        }
+    }
+
+    fn validate(&self) -> Result<(), GameError> {
+        match self {
+            SeriesFormat::BestOf(rounds) if *rounds == 0 || rounds % 2 == 0 => {
+                Err(GameError::BestOfNeedsOddRounds)
+            }
+            SeriesFormat::FirstTo(0) => Err(GameError::FirstToNeedsWins),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RoundOutcome {
+    pub winner: Team,
+    pub reason: WinReason,
+}
+
+/// Round wins per team across several games played by the same lobby.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Series {
+    format: SeriesFormat,
+    team_one_wins: u8,
+    team_two_wins: u8,
+    rounds: Vec<RoundOutcome>,
+}
+
+impl Series {
+    pub fn new(format: SeriesFormat) -> Result<Self, GameError> {
+        format.validate()?;
+        Ok(Series {
+            format,
+            team_one_wins: 0,
+            team_two_wins: 0,
+            rounds: Vec::new(),
+        })
+    }
+
+    pub fn format(&self) -> SeriesFormat {
+        self.format
+    }
+
+    pub fn get_team_one_wins(&self) -> u8 {
+        self.team_one_wins
+    }
+
+    pub fn get_team_two_wins(&self) -> u8 {
+        self.team_two_wins
+    }
+
+    pub fn rounds(&self) -> &[RoundOutcome] {
+        &self.rounds
+    }
+
+    pub fn winner(&self) -> Option<Team> {
+        let needed = self.format.wins_needed();
+        if self.team_one_wins >= needed {
+            Some(Team::TeamOne)
+        } else if self.team_two_wins >= needed {
+            Some(Team::TeamTwo)
+        } else {
+            None
+        }
+    }
+
+    pub fn is_decided(&self) -> bool {
+        self.winner().is_some()
+    }
+
+    /// Count a finished game towards the series. Returns the series winner once
+    /// it is decided.
+    pub fn record_round(&mut self, result: &GameResult) -> Result<Option<Team>, GameError> {
+        if self.is_decided() {
+            return Err(GameError::SeriesDecided);
+        }
+        match result.winner {
+            Team::TeamOne => self.team_one_wins += 1,
+            Team::TeamTwo => self.team_two_wins += 1,
+        }
+        self.rounds.push(RoundOutcome {
+            winner: result.winner,
+            reason: result.reason,
+        });
+        Ok(self.winner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(winner: Team, reason: WinReason) -> GameResult {
+        GameResult {
+            winner,
+            reason,
+            starting_team: Team::TeamOne,
+            team_one_score: 0,
+            team_two_score: 0,
+            team_one_players: vec![1, 3],
+            team_two_players: vec![2, 4],
+            team_one_spymaster: Some(1),
+            team_two_spymaster: Some(2),
+            player_stats: Default::default(),
+            handicap: None,
+        }
+    }
+
+    #[test]
+    fn series_format_validation() {
+        assert!(Series::new(SeriesFormat::BestOf(3)).is_ok());
+        assert!(Series::new(SeriesFormat::BestOf(7)).is_ok());
+        assert!(Series::new(SeriesFormat::BestOf(4)).is_err());
+        assert!(Series::new(SeriesFormat::BestOf(0)).is_err());
+        assert!(Series::new(SeriesFormat::FirstTo(0)).is_err());
+        assert_eq!(SeriesFormat::BestOf(5).wins_needed(), 3);
+        assert_eq!(SeriesFormat::FirstTo(4).wins_needed(), 4);
+    }
+
+    #[test]
+    fn series_best_of_three() -> Result<(), GameError> {
+        let mut series = Series::new(SeriesFormat::BestOf(3))?;
+        let res = series.record_round(&result(Team::TeamOne, WinReason::ScoreReached))?;
+        assert_eq!(res, None);
+        let res = series.record_round(&result(Team::TeamTwo, WinReason::OpponentDangerDraw))?;
+        assert_eq!(res, None);
+        assert!(!series.is_decided());
+        let res = series.record_round(&result(Team::TeamTwo, WinReason::ScoreReached))?;
+        assert_eq!(res, Some(Team::TeamTwo));
+        assert!(series.is_decided());
+        assert_eq!(series.get_team_one_wins(), 1);
+        assert_eq!(series.get_team_two_wins(), 2);
+        assert_eq!(series.rounds().len(), 3);
+        assert_eq!(series.rounds()[1].reason, WinReason::OpponentDangerDraw);
+        // no more rounds once decided.
+        assert!(series
+            .record_round(&result(Team::TeamOne, WinReason::ScoreReached))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn series_first_to() -> Result<(), GameError> {
+        let mut series = Series::new(SeriesFormat::FirstTo(2))?;
+        series.record_round(&result(Team::TeamOne, WinReason::ScoreReached))?;
+        series.record_round(&result(Team::TeamTwo, WinReason::ScoreReached))?;
+        series.record_round(&result(Team::TeamTwo, WinReason::ScoreReached))?;
+        assert_eq!(series.winner(), Some(Team::TeamTwo));
+        Ok(())
+    }
+}
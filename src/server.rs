@@ -0,0 +1,1406 @@
+use crate::accounts::AccountStore;
+use crate::clock::{Clock, SystemClock};
+use crate::errors::GameError;
+use crate::game::{Game, InProgressGame, InitialGame, TimerConfig};
+use crate::i18n::Locale;
+use crate::matchmaking::{MatchQueue, MatchmakingConfig, ProposedMatch};
+use crate::observer::EventLog;
+use crate::players::{Player, PlayerProfile, Presence};
+use crate::presence::{PresenceConfig, PresenceTracker};
+use crate::registry::PlayerRegistry;
+use crate::rules::RuleSet;
+use crate::session::{SessionStore, SessionToken};
+use crate::views::{PlayerView, SpectatorView, SpymasterView};
+use std::collections::{BTreeSet, HashMap};
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use warp::Filter;
+
+/// Session scope of account tokens. Lobby ids are numbers, so it never
+/// clashes with a lobby.
+const ACCOUNT_SCOPE: &str = "accounts";
+
+impl warp::reject::Reject for GameError {}
+
+/// Lobby games have no turn limits, but a spymaster who owes a clue for two
+/// minutes has the role offered to a teammate, and a team that does nothing
+/// for five loses its turn.
+const LOBBY_TIMERS: TimerConfig = TimerConfig {
+    turn_limit: None,
+    team_clock: None,
+    spymaster_idle: Some(Duration::from_secs(120)),
+    team_idle: Some(Duration::from_secs(300)),
+};
+
+/// A lobby's game, before or after it began. Players carry their registry
+/// profile, so views show their avatar and pronouns.
+enum LobbyGame {
+    Waiting(Game<InitialGame, PlayerProfile>),
+    Playing(Game<InProgressGame, PlayerProfile>),
+}
+
+fn find_player<S>(game: &Game<S, PlayerProfile>, player_id: u32) -> Option<PlayerProfile> {
+    game.get_team_one_players()
+        .into_iter()
+        .chain(game.get_team_two_players())
+        .chain(game.get_spectators())
+        .find(|p| *p.get_id() == player_id)
+        .cloned()
+}
+
+fn is_spectator<S>(game: &Game<S, PlayerProfile>, player_id: u32) -> bool {
+    game.get_spectators()
+        .into_iter()
+        .any(|p| *p.get_id() == player_id)
+}
+
+fn is_spymaster<S>(game: &Game<S, PlayerProfile>, player_id: u32) -> bool {
+    game.get_team_one_spymaster()
+        .into_iter()
+        .chain(game.get_team_two_spymaster())
+        .any(|p| *p.get_id() == player_id)
+}
+
+impl LobbyGame {
+    fn player_view(&self) -> PlayerView {
+        match self {
+            LobbyGame::Waiting(game) => game.player_view(),
+            LobbyGame::Playing(game) => game.player_view(),
+        }
+    }
+
+    fn spymaster_view(&self) -> SpymasterView {
+        match self {
+            LobbyGame::Waiting(game) => game.spymaster_view(),
+            LobbyGame::Playing(game) => game.spymaster_view(),
+        }
+    }
+
+    fn spectator_view(&self) -> SpectatorView {
+        match self {
+            LobbyGame::Waiting(game) => game.spectator_view(),
+            LobbyGame::Playing(game) => game.spectator_view(),
+        }
+    }
+
+    fn find_player(&self, player_id: u32) -> Option<PlayerProfile> {
+        match self {
+            LobbyGame::Waiting(game) => find_player(game, player_id),
+            LobbyGame::Playing(game) => find_player(game, player_id),
+        }
+    }
+
+    fn is_spectator(&self, player_id: u32) -> bool {
+        match self {
+            LobbyGame::Waiting(game) => is_spectator(game, player_id),
+            LobbyGame::Playing(game) => is_spectator(game, player_id),
+        }
+    }
+
+    fn is_spymaster(&self, player_id: u32) -> bool {
+        match self {
+            LobbyGame::Waiting(game) => is_spymaster(game, player_id),
+            LobbyGame::Playing(game) => is_spymaster(game, player_id),
+        }
+    }
+
+    fn add_spectator(&mut self, player: PlayerProfile) -> Result<(), GameError> {
+        match self {
+            LobbyGame::Waiting(game) => game.add_spectator(player),
+            LobbyGame::Playing(game) => game.add_spectator(player),
+        }
+    }
+
+    fn remove_spectator(&mut self, player: &PlayerProfile) {
+        match self {
+            LobbyGame::Waiting(game) => game.remove_spectator(player),
+            LobbyGame::Playing(game) => game.remove_spectator(player),
+        };
+    }
+
+    fn set_presence(&mut self, player_id: u32, presence: Presence) {
+        match self {
+            LobbyGame::Waiting(game) => game.set_presence(player_id, presence),
+            LobbyGame::Playing(game) => game.set_presence(player_id, presence),
+        }
+    }
+
+    fn waiting(&mut self) -> Result<&mut Game<InitialGame, PlayerProfile>, GameError> {
+        match self {
+            LobbyGame::Waiting(game) => Ok(game),
+            LobbyGame::Playing(_) => Err(GameError::GameAlreadyStarted),
+        }
+    }
+
+    fn playing(&mut self) -> Result<&mut Game<InProgressGame, PlayerProfile>, GameError> {
+        match self {
+            LobbyGame::Waiting(_) => Err(GameError::GameNotStarted),
+            LobbyGame::Playing(game) => Ok(game),
+        }
+    }
+}
+
+pub struct Lobby {
+    game: LobbyGame,
+    presence: PresenceTracker,
+    /// Team players who are ready to begin.
+    ready: BTreeSet<u32>,
+    events: EventLog,
+    /// Whether the finished game went into the registry yet.
+    recorded: bool,
+}
+
+/// Everything the server keeps: lobbies, sessions, players, accounts and
+/// the matchmaking queue. Lobby player ids are registry ids.
+pub struct ServerState {
+    lobbies: HashMap<String, Lobby>,
+    sessions: SessionStore,
+    registry: PlayerRegistry,
+    accounts: AccountStore,
+    queue: MatchQueue,
+    /// Lobbies the queue put players in, until they ask for their token.
+    matched: HashMap<u32, String>,
+    next_lobby_id: u32,
+    /// Times lobby games, presence and the queue.
+    clock: Arc<dyn Clock>,
+}
+
+pub type SharedState = Arc<Mutex<ServerState>>;
+
+impl Default for ServerState {
+    fn default() -> Self {
+        ServerState::new(PlayerRegistry::in_memory(), AccountStore::in_memory())
+    }
+}
+
+impl ServerState {
+    pub fn new(registry: PlayerRegistry, accounts: AccountStore) -> Self {
+        ServerState::with_clock(registry, accounts, Arc::new(SystemClock::new()))
+    }
+
+    /// Like `new`, with every timer in the server running on `clock`.
+    pub fn with_clock(
+        registry: PlayerRegistry,
+        accounts: AccountStore,
+        clock: Arc<dyn Clock>,
+    ) -> Self {
+        ServerState {
+            lobbies: HashMap::new(),
+            sessions: SessionStore::new(),
+            registry,
+            accounts,
+            queue: MatchQueue::new(MatchmakingConfig::default(), clock.clone())
+                .expect("default matchmaking config is valid"),
+            matched: HashMap::new(),
+            next_lobby_id: 0,
+            clock,
+        }
+    }
+
+    pub fn shared(self) -> SharedState {
+        Arc::new(Mutex::new(self))
+    }
+
+    /// Open an empty lobby and return its id.
+    fn create_lobby(&mut self) -> Result<String, GameError> {
+        let words: Vec<String> = (0..25).map(|x| format!("word-{}", x)).collect();
+        let mut game: Game<InitialGame, PlayerProfile> = Game::new(&words, RuleSet::classic())?;
+        game.set_clock(self.clock.clone());
+        game.set_timers(LOBBY_TIMERS);
+        game.set_spectators_see_key_after_end(true);
+        let events = EventLog::new();
+        game.add_observer(Box::new(events.clone()));
+        self.next_lobby_id += 1;
+        let lobby_id = format!("{}", self.next_lobby_id);
+        self.lobbies.insert(
+            lobby_id.clone(),
+            Lobby {
+                game: LobbyGame::Waiting(game),
+                presence: PresenceTracker::new(PresenceConfig::default(), self.clock.clone()),
+                ready: BTreeSet::new(),
+                events,
+                recorded: false,
+            },
+        );
+        Ok(lobby_id)
+    }
+
+    fn lobby(&self, lobby_id: &str) -> Result<&Lobby, GameError> {
+        self.lobbies.get(lobby_id).ok_or(GameError::LobbyNotFound)
+    }
+
+    fn lobby_mut(&mut self, lobby_id: &str) -> Result<&mut Lobby, GameError> {
+        self.lobbies
+            .get_mut(lobby_id)
+            .ok_or(GameError::LobbyNotFound)
+    }
+
+    /// The language a player picked, if the registry knows them.
+    fn player_locale(&self, player_id: u32) -> Option<Locale> {
+        self.registry.get(player_id).and_then(|p| p.get_locale())
+    }
+
+    /// The player behind `token`, which must have been issued for `lobby_id`.
+    fn authenticate(&self, lobby_id: &str, token: Option<SessionToken>) -> Result<u32, GameError> {
+        let token = token.ok_or(GameError::InvalidSession)?;
+        self.sessions.authenticate(&token, lobby_id)
+    }
+
+    /// The lobby player behind `token`.
+    fn lobby_player(
+        &self,
+        lobby_id: &str,
+        token: Option<SessionToken>,
+    ) -> Result<PlayerProfile, GameError> {
+        let player_id = self.authenticate(lobby_id, token)?;
+        self.lobby(lobby_id)?
+            .game
+            .find_player(player_id)
+            .ok_or(GameError::NotInGame)
+    }
+
+    /// Catch the lobby up with the clock: presence, timers and idle players,
+    /// and a game that just ended.
+    fn refresh(&mut self, lobby_id: &str) -> Result<(), GameError> {
+        self.sweep_presence(lobby_id)?;
+        if let LobbyGame::Playing(game) = &mut self.lobby_mut(lobby_id)?.game {
+            game.tick();
+        }
+        self.record_if_over(lobby_id)
+    }
+
+    /// Bring the lobby's presence up to date. Players whose grace period
+    /// ran out lose their sessions, and spectators their place; team seats
+    /// stay, shown offline.
+    fn sweep_presence(&mut self, lobby_id: &str) -> Result<(), GameError> {
+        let lobby = self
+            .lobbies
+            .get_mut(lobby_id)
+            .ok_or(GameError::LobbyNotFound)?;
+        let update = lobby.presence.sweep();
+        for (player_id, presence) in update.changed {
+            lobby.game.set_presence(player_id, presence);
+        }
+        for player_id in update.expired {
+            self.sessions.revoke_player(lobby_id, player_id);
+            let spectator = lobby.game.find_player(player_id);
+            if let Some(spectator) = spectator {
+                lobby.game.remove_spectator(&spectator);
+            }
+        }
+        Ok(())
+    }
+
+    /// Add a finished game to the history and ratings of its players, once.
+    fn record_if_over(&mut self, lobby_id: &str) -> Result<(), GameError> {
+        let lobby = self
+            .lobbies
+            .get_mut(lobby_id)
+            .ok_or(GameError::LobbyNotFound)?;
+        if lobby.recorded {
+            return Ok(());
+        }
+        let result = match &lobby.game {
+            LobbyGame::Playing(game) => game.result(),
+            LobbyGame::Waiting(_) => None,
+        };
+        if let Some(result) = result {
+            lobby.recorded = true;
+            self.registry.record_game(lobby_id, &result)?;
+        }
+        Ok(())
+    }
+
+    /// Seat a player in the lobby as a spectator and give them a session.
+    /// Players already in the lobby only get a new session.
+    fn join_lobby(&mut self, lobby_id: &str, player_id: u32) -> Result<SessionToken, GameError> {
+        let player = self
+            .registry
+            .get(player_id)
+            .map(|p| p.profile().clone())
+            .ok_or(GameError::UnknownPlayer(player_id))?;
+        let lobby = self
+            .lobbies
+            .get_mut(lobby_id)
+            .ok_or(GameError::LobbyNotFound)?;
+        if lobby.game.find_player(player_id).is_none() {
+            lobby.game.add_spectator(player)?;
+        }
+        lobby.presence.heartbeat(player_id);
+        lobby.game.set_presence(player_id, Presence::Online);
+        Ok(self.sessions.issue(lobby_id, player_id))
+    }
+
+    /// Begin the game once every team player is ready, both spymasters are
+    /// picked and the teams are big enough.
+    fn start_if_ready(&mut self, lobby_id: &str) -> Result<bool, GameError> {
+        let Lobby { game, ready, .. } = self.lobby_mut(lobby_id)?;
+        let game = game.waiting()?;
+        let everyone_ready = game
+            .get_team_one_players()
+            .into_iter()
+            .chain(game.get_team_two_players())
+            .all(|p| ready.contains(p.get_id()));
+        let spymasters =
+            game.get_team_one_spymaster().is_some() && game.get_team_two_spymaster().is_some();
+        if !everyone_ready || !spymasters || !game.can_begin() {
+            return Ok(false);
+        }
+        let mut lobby = self.lobbies.remove(lobby_id).unwrap();
+        lobby.game = match lobby.game {
+            LobbyGame::Waiting(game) => {
+                LobbyGame::Playing(game.begin().expect("checked by can_begin"))
+            }
+            playing => playing,
+        };
+        self.lobbies.insert(String::from(lobby_id), lobby);
+        Ok(true)
+    }
+
+    /// Put a matched group into a fresh lobby, teams and spymasters set.
+    fn open_match(&mut self, proposed: &ProposedMatch) -> Result<String, GameError> {
+        let registry = &self.registry;
+        let profiles = |team: &[u32]| -> Result<Vec<PlayerProfile>, GameError> {
+            team.iter()
+                .map(|id| {
+                    registry
+                        .get(*id)
+                        .map(|p| p.profile().clone())
+                        .ok_or(GameError::UnknownPlayer(*id))
+                })
+                .collect()
+        };
+        let team_one = profiles(&proposed.team_one)?;
+        let team_two = profiles(&proposed.team_two)?;
+        let lobby_id = self.create_lobby()?;
+        let lobby = self.lobbies.get_mut(&lobby_id).unwrap();
+        let game = lobby.game.waiting()?;
+        for player in team_one {
+            game.add_player_team_one(player);
+        }
+        for player in team_two {
+            game.add_player_team_two(player);
+        }
+        // teams are ordered by id, so is the spymaster index.
+        let index =
+            |team: &[u32], spymaster: u32| team.iter().filter(|id| **id < spymaster).count();
+        game.set_team_one_spymaster(index(&proposed.team_one, proposed.team_one_spymaster))?;
+        game.set_team_two_spymaster(index(&proposed.team_two, proposed.team_two_spymaster))?;
+        for id in proposed.team_one.iter().chain(proposed.team_two.iter()) {
+            self.matched.insert(*id, lobby_id.clone());
+        }
+        Ok(lobby_id)
+    }
+
+    /// Form whatever games the queue allows right now.
+    fn run_matchmaking(&mut self) -> Result<(), GameError> {
+        for proposed in self.queue.poll().iter() {
+            self.open_match(proposed)?;
+        }
+        Ok(())
+    }
+
+    fn queue_status(&mut self, player_id: u32) -> Result<handlers::QueueStatus, GameError> {
+        self.run_matchmaking()?;
+        if let Some(lobby_id) = self.matched.remove(&player_id) {
+            let token = self.sessions.issue(&lobby_id, player_id);
+            return Ok(handlers::QueueStatus::Matched {
+                lobby_id,
+                player_id,
+                token,
+            });
+        }
+        let position = self.queue.position(player_id).ok_or(GameError::NotQueued)?;
+        let waited = self.queue.waited(player_id).unwrap_or_default();
+        Ok(handlers::QueueStatus::Waiting {
+            position,
+            waited_secs: waited.as_secs(),
+        })
+    }
+}
+
+/// Every route in api.md, with game errors turned into problem JSON in the
+/// language of the request or else the player's.
+pub fn api(
+    state: SharedState,
+) -> impl Filter<Extract = impl warp::Reply, Error = Infallible> + Clone {
+    let app = filters::app(state.clone())
+        .map(|reply| Ok(warp::Reply::into_response(reply)))
+        .recover(|err| async move { Ok::<_, Infallible>(Err(err)) })
+        .unify();
+    filters::error_locale(state)
+        .and(app)
+        .and_then(handlers::handle_rejection)
+}
+
+mod filters {
+    use super::handlers;
+    use super::SharedState;
+    use crate::i18n::Locale;
+    use crate::session::SessionToken;
+    use std::convert::Infallible;
+    use warp::http::HeaderMap;
+    use warp::Filter;
+
+    pub fn app(
+        state: SharedState,
+    ) -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+        let lobbies = lobby(state.clone())
+            .or(create_player(state.clone()))
+            .or(leave(state.clone()))
+            .or(heartbeat(state.clone()))
+            .or(disconnect(state.clone()))
+            .boxed();
+        let setup = join_team(state.clone())
+            .or(become_spymaster(state.clone()))
+            .or(decline_spymaster(state.clone()))
+            .or(ready(state.clone()))
+            .boxed();
+        let play = player_view(state.clone())
+            .or(spymaster_view(state.clone()))
+            .or(clue(state.clone()))
+            .or(unravel(state.clone()))
+            .or(end_turn(state.clone()))
+            .or(action_logs(state.clone()))
+            .boxed();
+        let accounts = guest(state.clone())
+            .or(sign_up(state.clone()))
+            .or(login(state.clone()))
+            .or(upgrade(state.clone()))
+            .or(leaderboard(state.clone()))
+            .boxed();
+        let matchmaking = join_queue(state.clone())
+            .or(queue_status(state.clone()))
+            .or(leave_queue(state))
+            .boxed();
+        lobbies.or(setup).or(play).or(accounts).or(matchmaking)
+    }
+
+    fn with_state(
+        state: SharedState,
+    ) -> impl Filter<Extract = (SharedState,), Error = std::convert::Infallible> + Clone {
+        warp::any().map(move || state.clone())
+    }
+
+    /// The token in a `Bearer` authorization header.
+    fn bearer_token(header: &str) -> Option<SessionToken> {
+        header
+            .strip_prefix("Bearer ")
+            .map(|token| SessionToken::from(token.trim()))
+    }
+
+    /// The bearer token from the `Authorization` header, if any.
+    fn session_token(
+    ) -> impl Filter<Extract = (Option<SessionToken>,), Error = warp::Rejection> + Clone {
+        warp::header::optional::<String>("authorization")
+            .map(|header: Option<String>| header.and_then(|h| bearer_token(&h)))
+    }
+
+    /// The language asked for in `Accept-Language`, if we have it.
+    fn accept_language() -> impl Filter<Extract = (Option<Locale>,), Error = warp::Rejection> + Clone
+    {
+        warp::header::optional::<String>("accept-language")
+            .map(|header: Option<String>| header.and_then(|h| Locale::from_accept_language(&h)))
+    }
+
+    /// The language for error replies, picked like the action logs do: the
+    /// request's, else that of the player behind the token. Never rejects,
+    /// so it can run ahead of every route.
+    pub fn error_locale(
+        state: SharedState,
+    ) -> impl Filter<Extract = (Locale,), Error = Infallible> + Clone {
+        warp::header::headers_cloned().and(with_state(state)).map(
+            |headers: HeaderMap, state: SharedState| {
+                let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+                let request = header("accept-language").and_then(Locale::from_accept_language);
+                let state = state.lock().unwrap();
+                let player = header("authorization")
+                    .and_then(bearer_token)
+                    .and_then(|token| state.sessions.get(&token).map(|s| s.player_id))
+                    .and_then(|player_id| state.player_locale(player_id));
+                Locale::pick(request, player)
+            },
+        )
+    }
+
+    pub fn lobby(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby")
+            .and(warp::filters::method::post())
+            .and(with_state(state))
+            .and_then(handlers::create_lobby)
+    }
+
+    pub fn create_player(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "players")
+            .and(warp::filters::method::post())
+            .and(warp::body::json())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::create_player)
+    }
+
+    pub fn leave(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "session")
+            .and(warp::filters::method::delete())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::revoke_session)
+    }
+
+    pub fn heartbeat(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "heartbeat")
+            .and(warp::filters::method::post())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::heartbeat)
+    }
+
+    pub fn disconnect(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "disconnect")
+            .and(warp::filters::method::post())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::disconnect)
+    }
+
+    pub fn join_team(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "joinTeam")
+            .and(warp::filters::method::post())
+            .and(warp::body::json())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::join_team)
+    }
+
+    pub fn become_spymaster(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "becomeSpymaster")
+            .and(warp::filters::method::post())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::become_spymaster)
+    }
+
+    pub fn decline_spymaster(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "declineSpymaster")
+            .and(warp::filters::method::post())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::decline_spymaster)
+    }
+
+    pub fn ready(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "ready")
+            .and(warp::filters::method::put())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::ready)
+    }
+
+    pub fn player_view(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "gameviews" / "player")
+            .and(warp::filters::method::get())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::player_view)
+    }
+
+    pub fn spymaster_view(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "gameviews" / "spymaster")
+            .and(warp::filters::method::get())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::spymaster_view)
+    }
+
+    pub fn clue(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "clue")
+            .and(warp::filters::method::post())
+            .and(warp::body::json())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::give_clue)
+    }
+
+    pub fn unravel(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "unravel")
+            .and(warp::filters::method::post())
+            .and(warp::body::json())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::unravel)
+    }
+
+    pub fn end_turn(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "endTurn")
+            .and(warp::filters::method::post())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::end_turn)
+    }
+
+    pub fn action_logs(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("lobby" / String / "actionLogs")
+            .and(warp::filters::method::get())
+            .and(session_token())
+            .and(accept_language())
+            .and(with_state(state))
+            .and_then(handlers::action_logs)
+    }
+
+    pub fn guest(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("accounts" / "guest")
+            .and(warp::filters::method::post())
+            .and(warp::body::json())
+            .and(with_state(state))
+            .and_then(handlers::create_guest)
+    }
+
+    pub fn sign_up(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("accounts" / "signup")
+            .and(warp::filters::method::post())
+            .and(warp::body::json())
+            .and(with_state(state))
+            .and_then(handlers::sign_up)
+    }
+
+    pub fn login(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("accounts" / "login")
+            .and(warp::filters::method::post())
+            .and(warp::body::json())
+            .and(with_state(state))
+            .and_then(handlers::login)
+    }
+
+    pub fn upgrade(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("accounts" / "upgrade")
+            .and(warp::filters::method::post())
+            .and(warp::body::json())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::upgrade)
+    }
+
+    pub fn leaderboard(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("leaderboard")
+            .and(warp::filters::method::get())
+            .and(warp::query())
+            .and(with_state(state))
+            .and_then(handlers::leaderboard)
+    }
+
+    pub fn join_queue(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("matchmaking" / "queue")
+            .and(warp::filters::method::post())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::join_queue)
+    }
+
+    pub fn queue_status(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("matchmaking" / "queue")
+            .and(warp::filters::method::get())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::queue_status)
+    }
+
+    pub fn leave_queue(
+        state: SharedState,
+    ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+        warp::path!("matchmaking" / "queue")
+            .and(warp::filters::method::delete())
+            .and(session_token())
+            .and(with_state(state))
+            .and_then(handlers::leave_queue)
+    }
+}
+
+mod handlers {
+    use super::{LobbyGame, SharedState, ACCOUNT_SCOPE};
+    use crate::accounts::hash_password;
+    use crate::errors::GameError;
+    use crate::game::Team;
+    use crate::i18n::{Locale, Localized};
+    use crate::observer::GameEvent;
+    use crate::players::{DisplayName, Player, Presence};
+    use crate::ratings::Role;
+    use crate::session::SessionToken;
+    use crate::storage::storage_error;
+    use crate::views::SpectatorView;
+    use serde::{Deserialize, Serialize};
+    use std::convert::Infallible;
+    use warp::http::StatusCode;
+    use warp::reply::Response;
+    use warp::Reply;
+
+    const MAX_LEADERBOARD: usize = 100;
+
+    /// Guests send a name. Players with an account send their account
+    /// token instead and may leave the name out.
+    #[derive(Deserialize)]
+    pub struct JoinRequest {
+        name: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    pub struct GuestRequest {
+        name: String,
+    }
+
+    #[derive(Serialize)]
+    struct Joined {
+        player_id: u32,
+        token: SessionToken,
+    }
+
+    #[derive(Deserialize)]
+    pub struct SignUpRequest {
+        username: String,
+        password: String,
+        name: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct Credentials {
+        username: String,
+        password: String,
+    }
+
+    #[derive(Deserialize)]
+    pub struct JoinTeamRequest {
+        team: Team,
+    }
+
+    #[derive(Deserialize)]
+    pub struct ClueRequest {
+        word: String,
+        number: u8,
+    }
+
+    #[derive(Deserialize)]
+    pub struct UnravelRequest {
+        tile: u8,
+    }
+
+    #[derive(Deserialize)]
+    pub struct LeaderboardQuery {
+        role: Option<Role>,
+        limit: Option<usize>,
+    }
+
+    #[derive(Serialize)]
+    struct ReadyState {
+        ready: Vec<u32>,
+        started: bool,
+    }
+
+    /// An action log entry with its message in the reader's language.
+    #[derive(Serialize)]
+    struct LoggedEvent {
+        event: GameEvent,
+        message: String,
+    }
+
+    #[derive(Serialize)]
+    #[serde(tag = "status", rename_all = "snake_case")]
+    pub enum QueueStatus {
+        Waiting {
+            position: usize,
+            waited_secs: u64,
+        },
+        /// `token` is the session for the new lobby.
+        Matched {
+            lobby_id: String,
+            player_id: u32,
+            token: SessionToken,
+        },
+    }
+
+    pub async fn create_lobby(state: SharedState) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        let lobby_id = state.create_lobby().map_err(warp::reject::custom)?;
+        Ok(warp::reply::with_status(
+            warp::reply::json(&lobby_id),
+            StatusCode::CREATED,
+        ))
+    }
+
+    /// Join a lobby as a spectator, as a new guest or with an account
+    /// token. The reply carries the session token all later requests need.
+    pub async fn create_player(
+        lobby_id: String,
+        request: JoinRequest,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player_id = match token {
+            Some(token) => state
+                .sessions
+                .authenticate(&token, ACCOUNT_SCOPE)
+                .map_err(warp::reject::custom)?,
+            None => {
+                let name = request.name.unwrap_or_default();
+                let name = DisplayName::new(&name).map_err(warp::reject::custom)?;
+                *state
+                    .registry
+                    .register(name)
+                    .map_err(warp::reject::custom)?
+                    .get_id()
+            }
+        };
+        let token = state
+            .join_lobby(&lobby_id, player_id)
+            .map_err(warp::reject::custom)?;
+        Ok(warp::reply::with_status(
+            warp::reply::json(&Joined { player_id, token }),
+            StatusCode::CREATED,
+        ))
+    }
+
+    pub async fn revoke_session(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state
+            .authenticate(&lobby_id, token.clone())
+            .map_err(warp::reject::custom)?;
+        state.sessions.revoke(&token.unwrap());
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Keep the player's seat. A reloaded page reclaims it by sending a
+    /// heartbeat with its old session token within the grace period.
+    pub async fn heartbeat(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player_id = state
+            .authenticate(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        let lobby = state.lobby_mut(&lobby_id).map_err(warp::reject::custom)?;
+        lobby.presence.heartbeat(player_id);
+        lobby.game.set_presence(player_id, Presence::Online);
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// The page is going away; start the grace period now.
+    pub async fn disconnect(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player_id = state
+            .authenticate(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        let lobby = state.lobby_mut(&lobby_id).map_err(warp::reject::custom)?;
+        lobby.presence.disconnect(player_id);
+        let presence = lobby.presence.status(player_id);
+        lobby.game.set_presence(player_id, presence);
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Move from the spectators onto a team before the game begins.
+    pub async fn join_team(
+        lobby_id: String,
+        request: JoinTeamRequest,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player = state
+            .lobby_player(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        let game = state
+            .lobby_mut(&lobby_id)
+            .and_then(|lobby| lobby.game.waiting())
+            .map_err(warp::reject::custom)?;
+        if game.get_player_team(&player).is_some() {
+            return Err(warp::reject::custom(GameError::AlreadyOnTeam));
+        }
+        match request.team {
+            Team::TeamOne => game.add_player_team_one(player),
+            Team::TeamTwo => game.add_player_team_two(player),
+        }
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Take the spymaster role of your team before the game begins, or
+    /// accept it once offered because the spymaster went idle.
+    pub async fn become_spymaster(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player = state
+            .lobby_player(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        let lobby = state.lobby_mut(&lobby_id).map_err(warp::reject::custom)?;
+        match &mut lobby.game {
+            LobbyGame::Waiting(game) => {
+                if game.is_spectator(&player) {
+                    return Err(warp::reject::custom(GameError::SpectatorCannotAct));
+                }
+                let team = game
+                    .get_player_team(&player)
+                    .ok_or_else(|| warp::reject::custom(GameError::NotInGame))?;
+                let players = match team {
+                    Team::TeamOne => game.get_team_one_players(),
+                    Team::TeamTwo => game.get_team_two_players(),
+                };
+                let ind = players
+                    .iter()
+                    .position(|p| p.get_id() == player.get_id())
+                    .ok_or_else(|| warp::reject::custom(GameError::NotInGame))?;
+                match team {
+                    Team::TeamOne => game.set_team_one_spymaster(ind),
+                    Team::TeamTwo => game.set_team_two_spymaster(ind),
+                }
+                .map_err(warp::reject::custom)?;
+            }
+            LobbyGame::Playing(game) => game
+                .accept_spymaster_offer(&player)
+                .map_err(warp::reject::custom)?,
+        }
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Turn down the spymaster role offered because the spymaster went idle.
+    pub async fn decline_spymaster(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player = state
+            .lobby_player(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        state
+            .lobby_mut(&lobby_id)
+            .and_then(|lobby| lobby.game.playing())
+            .and_then(|game| game.decline_spymaster_offer(&player))
+            .map_err(warp::reject::custom)?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Mark yourself ready. The game begins once every team player is.
+    pub async fn ready(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player = state
+            .lobby_player(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        let lobby = state.lobby_mut(&lobby_id).map_err(warp::reject::custom)?;
+        let game = lobby.game.waiting().map_err(warp::reject::custom)?;
+        if game.is_spectator(&player) {
+            return Err(warp::reject::custom(GameError::SpectatorCannotAct));
+        }
+        lobby.ready.insert(*player.get_id());
+        let started = state
+            .start_if_ready(&lobby_id)
+            .map_err(warp::reject::custom)?;
+        let ready = state
+            .lobby(&lobby_id)
+            .map_err(warp::reject::custom)?
+            .ready
+            .iter()
+            .cloned()
+            .collect();
+        Ok(warp::reply::json(&ReadyState { ready, started }))
+    }
+
+    /// Spectators see the key card too once the game is over.
+    pub async fn player_view(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player_id = state
+            .authenticate(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        let lobby = state.lobby(&lobby_id).map_err(warp::reject::custom)?;
+        if !lobby.game.is_spectator(player_id) {
+            return Ok(warp::reply::json(&lobby.game.player_view()));
+        }
+        Ok(match lobby.game.spectator_view() {
+            SpectatorView::Plain(view) => warp::reply::json(&view),
+            SpectatorView::Full(view) => warp::reply::json(&view),
+        })
+    }
+
+    /// The key card. Only for spymasters.
+    pub async fn spymaster_view(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player_id = state
+            .authenticate(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        let lobby = state.lobby(&lobby_id).map_err(warp::reject::custom)?;
+        if !lobby.game.is_spymaster(player_id) {
+            return Err(warp::reject::custom(GameError::NotSpymaster));
+        }
+        Ok(warp::reply::json(&lobby.game.spymaster_view()))
+    }
+
+    pub async fn give_clue(
+        lobby_id: String,
+        request: ClueRequest,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player = state
+            .lobby_player(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        state
+            .lobby_mut(&lobby_id)
+            .and_then(|lobby| lobby.game.playing())
+            .and_then(|game| game.give_clue(&player, &request.word, request.number))
+            .map_err(warp::reject::custom)?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Reveal a tile. Replies with the player view after the move.
+    pub async fn unravel(
+        lobby_id: String,
+        request: UnravelRequest,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player = state
+            .lobby_player(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        state
+            .lobby_mut(&lobby_id)
+            .and_then(|lobby| lobby.game.playing())
+            .and_then(|game| game.try_unravel(&player, request.tile))
+            .map_err(warp::reject::custom)?;
+        state
+            .record_if_over(&lobby_id)
+            .map_err(warp::reject::custom)?;
+        let lobby = state.lobby(&lobby_id).map_err(warp::reject::custom)?;
+        Ok(warp::reply::json(&lobby.game.player_view()))
+    }
+
+    pub async fn end_turn(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player = state
+            .lobby_player(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        state
+            .lobby_mut(&lobby_id)
+            .and_then(|lobby| lobby.game.playing())
+            .and_then(|game| game.end_turn(&player))
+            .map_err(warp::reject::custom)?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Everything that happened in the lobby, oldest first, in the language
+    /// of the request or else the player's.
+    pub async fn action_logs(
+        lobby_id: String,
+        token: Option<SessionToken>,
+        locale: Option<Locale>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        state.refresh(&lobby_id).map_err(warp::reject::custom)?;
+        let player_id = state
+            .authenticate(&lobby_id, token)
+            .map_err(warp::reject::custom)?;
+        let locale = Locale::pick(locale, state.player_locale(player_id));
+        let lobby = state.lobby(&lobby_id).map_err(warp::reject::custom)?;
+        let events: Vec<LoggedEvent> = lobby
+            .events
+            .events()
+            .into_iter()
+            .map(|event| LoggedEvent {
+                message: event.localize(locale),
+                event,
+            })
+            .collect();
+        Ok(warp::reply::json(&events))
+    }
+
+    /// Register a guest: a player with history but no login.
+    pub async fn create_guest(
+        request: GuestRequest,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let name = DisplayName::new(&request.name).map_err(warp::reject::custom)?;
+        let mut state = state.lock().unwrap();
+        let player_id = *state
+            .registry
+            .register(name)
+            .map_err(warp::reject::custom)?
+            .get_id();
+        let token = state.sessions.issue(ACCOUNT_SCOPE, player_id);
+        Ok(warp::reply::with_status(
+            warp::reply::json(&Joined { player_id, token }),
+            StatusCode::CREATED,
+        ))
+    }
+
+    /// Argon2 is slow on purpose, so hashing runs on the blocking pool and
+    /// never while the state is locked.
+    async fn off_thread<T, F>(f: F) -> Result<T, warp::Rejection>
+    where
+        F: FnOnce() -> Result<T, GameError> + Send + 'static,
+        T: Send + 'static,
+    {
+        tokio::task::spawn_blocking(f)
+            .await
+            .map_err(storage_error)
+            .and_then(|result| result)
+            .map_err(warp::reject::custom)
+    }
+
+    pub async fn sign_up(
+        request: SignUpRequest,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let name = DisplayName::new(&request.name).map_err(warp::reject::custom)?;
+        state
+            .lock()
+            .unwrap()
+            .accounts
+            .check_username(&request.username)
+            .map_err(warp::reject::custom)?;
+        let password = request.password;
+        let password = off_thread(move || hash_password(&password)).await?;
+        let mut state = state.lock().unwrap();
+        let state = &mut *state;
+        let player_id = state
+            .accounts
+            .sign_up(&mut state.registry, &request.username, password, name)
+            .map_err(warp::reject::custom)?;
+        let token = state.sessions.issue(ACCOUNT_SCOPE, player_id);
+        Ok(warp::reply::with_status(
+            warp::reply::json(&Joined { player_id, token }),
+            StatusCode::CREATED,
+        ))
+    }
+
+    pub async fn login(
+        request: Credentials,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let attempt = state
+            .lock()
+            .unwrap()
+            .accounts
+            .login_attempt(&request.username);
+        let password = request.password;
+        let player_id = off_thread(move || attempt.verify(&password)).await?;
+        let token = state
+            .lock()
+            .unwrap()
+            .sessions
+            .issue(ACCOUNT_SCOPE, player_id);
+        Ok(warp::reply::json(&Joined { player_id, token }))
+    }
+
+    /// Give the guest behind the token a login, keeping their history.
+    pub async fn upgrade(
+        request: Credentials,
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let guest_id = {
+            let state = state.lock().unwrap();
+            let guest_id = state
+                .authenticate(ACCOUNT_SCOPE, token)
+                .map_err(warp::reject::custom)?;
+            state
+                .accounts
+                .check_upgrade(&state.registry, guest_id, &request.username)
+                .map_err(warp::reject::custom)?;
+            guest_id
+        };
+        let password = request.password;
+        let password = off_thread(move || hash_password(&password)).await?;
+        let mut state = state.lock().unwrap();
+        let state = &mut *state;
+        state
+            .accounts
+            .upgrade_guest(&state.registry, guest_id, &request.username, password)
+            .map_err(warp::reject::custom)?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// Best rated registered players, guessers unless `role=Spymaster`.
+    pub async fn leaderboard(
+        query: LeaderboardQuery,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let role = query.role.unwrap_or(Role::Guesser);
+        let limit = query.limit.unwrap_or(20).min(MAX_LEADERBOARD);
+        let state = state.lock().unwrap();
+        Ok(warp::reply::json(&state.registry.leaderboard(role, limit)))
+    }
+
+    /// Queue the account behind the token. Matching happens right away if
+    /// enough players are waiting.
+    pub async fn join_queue(
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        let state = &mut *state;
+        let player_id = state
+            .authenticate(ACCOUNT_SCOPE, token)
+            .map_err(warp::reject::custom)?;
+        if state.matched.contains_key(&player_id) {
+            return Err(warp::reject::custom(GameError::AlreadyQueued));
+        }
+        let ratings = state
+            .registry
+            .get(player_id)
+            .map(|p| *p.ratings())
+            .ok_or_else(|| warp::reject::custom(GameError::UnknownPlayer(player_id)))?;
+        state
+            .queue
+            .join(player_id, ratings)
+            .map_err(warp::reject::custom)?;
+        let status = state
+            .queue_status(player_id)
+            .map_err(warp::reject::custom)?;
+        Ok(warp::reply::with_status(
+            warp::reply::json(&status),
+            StatusCode::ACCEPTED,
+        ))
+    }
+
+    /// Where the player stands in the queue, or their lobby once matched.
+    pub async fn queue_status(
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        let player_id = state
+            .authenticate(ACCOUNT_SCOPE, token)
+            .map_err(warp::reject::custom)?;
+        let status = state
+            .queue_status(player_id)
+            .map_err(warp::reject::custom)?;
+        Ok(warp::reply::json(&status))
+    }
+
+    pub async fn leave_queue(
+        token: Option<SessionToken>,
+        state: SharedState,
+    ) -> Result<impl Reply, warp::Rejection> {
+        let mut state = state.lock().unwrap();
+        let player_id = state
+            .authenticate(ACCOUNT_SCOPE, token)
+            .map_err(warp::reject::custom)?;
+        state.queue.leave(player_id).map_err(warp::reject::custom)?;
+        Ok(StatusCode::NO_CONTENT)
+    }
+
+    /// The problem body as a reply, with the matching status code.
+    fn to_reply_in(
+        error: &GameError,
+        locale: Locale,
+    ) -> warp::reply::WithStatus<warp::reply::Json> {
+        let status =
+            StatusCode::from_u16(error.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+        warp::reply::with_status(warp::reply::json(&error.problem_in(locale)), status)
+    }
+
+    /// Game errors become problem JSON with their status, everything else
+    /// keeps warp's usual status.
+    pub async fn handle_rejection(
+        locale: Locale,
+        reply: Result<Response, warp::Rejection>,
+    ) -> Result<Response, Infallible> {
+        let err = match reply {
+            Ok(reply) => return Ok(reply),
+            Err(err) => err,
+        };
+        if let Some(e) = err.find::<GameError>() {
+            return Ok(to_reply_in(e, locale).into_response());
+        }
+        let status = if err.is_not_found() {
+            StatusCode::NOT_FOUND
+        } else {
+            StatusCode::BAD_REQUEST
+        };
+        Ok(warp::reply::with_status(String::from(status.as_str()), status).into_response())
+    }
+}
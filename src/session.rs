@@ -0,0 +1,166 @@
+use crate::errors::GameError;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+const TOKEN_BYTES: usize = 32;
+
+/// An unguessable bearer token, 32 random bytes from the OS as hex.
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SessionToken(String);
+
+impl SessionToken {
+    fn generate() -> Self {
+        let mut bytes = [0u8; TOKEN_BYTES];
+        OsRng.fill_bytes(&mut bytes);
+        SessionToken(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0[..]
+    }
+}
+
+impl From<&str> for SessionToken {
+    fn from(token: &str) -> Self {
+        SessionToken(String::from(token))
+    }
+}
+
+impl fmt::Display for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// tokens are secrets, keep them out of logs.
+impl fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let prefix: String = self.0.chars().take(6).collect();
+        write!(f, "SessionToken({}…)", prefix)
+    }
+}
+
+/// Who a token speaks for.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub lobby_id: String,
+    pub player_id: u32,
+}
+
+/// Issued sessions. A token only works for the lobby it was issued in.
+#[derive(Debug, Default)]
+pub struct SessionStore {
+    sessions: HashMap<SessionToken, Session>,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        SessionStore::default()
+    }
+
+    pub fn issue(&mut self, lobby_id: &str, player_id: u32) -> SessionToken {
+        let token = SessionToken::generate();
+        self.sessions.insert(
+            token.clone(),
+            Session {
+                lobby_id: String::from(lobby_id),
+                player_id,
+            },
+        );
+        token
+    }
+
+    pub fn get(&self, token: &SessionToken) -> Option<&Session> {
+        self.sessions.get(token)
+    }
+
+    /// The player `token` belongs to, if it was issued for `lobby_id` and
+    /// hasn't been revoked.
+    pub fn authenticate(&self, token: &SessionToken, lobby_id: &str) -> Result<u32, GameError> {
+        match self.sessions.get(token) {
+            Some(session) if session.lobby_id == lobby_id => Ok(session.player_id),
+            Some(_) => Err(GameError::SessionLobbyMismatch),
+            None => Err(GameError::InvalidSession),
+        }
+    }
+
+    pub fn revoke(&mut self, token: &SessionToken) -> bool {
+        self.sessions.remove(token).is_some()
+    }
+
+    /// Revoke every session of a player in a lobby, e.g. when they are kicked.
+    pub fn revoke_player(&mut self, lobby_id: &str, player_id: u32) -> usize {
+        let before = self.sessions.len();
+        self.sessions
+            .retain(|_, s| !(s.lobby_id == lobby_id && s.player_id == player_id));
+        before - self.sessions.len()
+    }
+
+    /// Revoke all sessions of a lobby, e.g. when it closes.
+    pub fn revoke_lobby(&mut self, lobby_id: &str) -> usize {
+        let before = self.sessions.len();
+        self.sessions.retain(|_, s| s.lobby_id != lobby_id);
+        before - self.sessions.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_tokens_are_unguessable() {
+        let mut store = SessionStore::new();
+        let first = store.issue("lobby", 1);
+        let second = store.issue("lobby", 1);
+        assert_ne!(first, second);
+        assert_eq!(first.as_str().len(), TOKEN_BYTES * 2);
+        assert!(first.as_str().chars().all(|c| c.is_ascii_hexdigit()));
+        assert!(!format!("{:?}", first).contains(first.as_str()));
+    }
+
+    #[test]
+    fn session_authenticate_and_revoke() {
+        let mut store = SessionStore::new();
+        let token = store.issue("lobby-a", 7);
+        assert_eq!(store.authenticate(&token, "lobby-a"), Ok(7));
+        assert_eq!(
+            store.authenticate(&token, "lobby-b"),
+            Err(GameError::SessionLobbyMismatch)
+        );
+        assert_eq!(
+            store.authenticate(&SessionToken::from("forged"), "lobby-a"),
+            Err(GameError::InvalidSession)
+        );
+        assert!(store.revoke(&token));
+        assert!(!store.revoke(&token));
+        assert_eq!(
+            store.authenticate(&token, "lobby-a"),
+            Err(GameError::InvalidSession)
+        );
+    }
+
+    #[test]
+    fn session_bulk_revoke() {
+        let mut store = SessionStore::new();
+        store.issue("lobby-a", 1);
+        store.issue("lobby-a", 1);
+        store.issue("lobby-a", 2);
+        let other = store.issue("lobby-b", 1);
+        assert_eq!(store.revoke_player("lobby-a", 1), 2);
+        assert_eq!(store.revoke_lobby("lobby-a"), 1);
+        assert_eq!(store.len(), 1);
+        assert!(store.get(&other).is_some());
+    }
+}
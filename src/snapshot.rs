@@ -0,0 +1,297 @@
+use crate::board::Board;
+use crate::clock::Clock;
+use crate::errors::{ErrorSource, GameError};
+use crate::game::{
+    Clue, Game, GameResult, InProgressGame, InitialGame, Team, TimerConfig, UnravelRecord,
+    WinReason,
+};
+use crate::players::Player;
+use crate::rules::RuleSet;
+use crate::stats::PlayerStats;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Bumped whenever the snapshot layout changes. Older versions must keep
+/// restoring, newer ones are rejected.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Serializable copy of a game and its players, in any typestate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameSnapshot<P> {
+    pub version: u32,
+    pub started: bool,
+    pub board: Board,
+    pub team_one_players: Vec<P>,
+    pub team_two_players: Vec<P>,
+    pub spectators: Vec<P>,
+    pub spectators_see_key_after_end: bool,
+    pub team_one_spymaster: Option<u32>,
+    pub team_two_spymaster: Option<u32>,
+    pub team_one_score: u8,
+    pub team_two_score: u8,
+    pub next_turn: Option<Team>,
+    pub starting_team: Team,
+    pub host_id: Option<u32>,
+    pub winner: Option<(Team, WinReason)>,
+    pub unravel_history: Vec<UnravelRecord>,
+    pub round_history: Vec<GameResult>,
+    pub timers: TimerConfig,
+    /// How long the turn in progress had run.
+    pub turn_elapsed: Duration,
+    pub team_one_time_left: Option<Duration>,
+    pub team_two_time_left: Option<Duration>,
+    pub rules: RuleSet,
+    pub current_clue: Option<Clue>,
+    pub guesses_left: Option<u8>,
+    pub player_stats: BTreeMap<u32, PlayerStats>,
+}
+
+/// A game brought back from a snapshot, in whichever typestate it was saved.
+#[derive(Debug)]
+pub enum RestoredGame<P: Player> {
+    Initial(Game<InitialGame, P>),
+    InProgress(Game<InProgressGame, P>),
+}
+
+impl<P: Player> GameSnapshot<P> {
+    pub fn check_version(&self) -> Result<(), GameError> {
+        if self.version == 0 || self.version > SNAPSHOT_VERSION {
+            return Err(GameError::UnsupportedSnapshotVersion(self.version));
+        }
+        Ok(())
+    }
+
+    pub fn restore(self, clock: Arc<dyn Clock>) -> Result<RestoredGame<P>, GameError> {
+        if self.started {
+            Ok(RestoredGame::InProgress(
+                Game::<InProgressGame, P>::restore(self, clock)?,
+            ))
+        } else {
+            Ok(RestoredGame::Initial(Game::<InitialGame, P>::restore(
+                self, clock,
+            )?))
+        }
+    }
+}
+
+impl<P> GameSnapshot<P>
+where
+    P: Player + Serialize + for<'de> Deserialize<'de>,
+{
+    pub fn to_json(&self) -> Result<String, GameError> {
+        serde_json::to_string(self).map_err(|e| GameError::Serialization(ErrorSource::new(e)))
+    }
+
+    pub fn from_json(data: &str) -> Result<Self, GameError> {
+        let snapshot: GameSnapshot<P> = serde_json::from_str(data)
+            .map_err(|e| GameError::Serialization(ErrorSource::new(e)))?;
+        snapshot.check_version()?;
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::game::test_support::{setup_game, words};
+    use crate::game::RematchOptions;
+    use crate::players::SimplePlayer;
+    use std::sync::Arc;
+
+    fn clock() -> Arc<dyn Clock> {
+        Arc::new(ManualClock::new())
+    }
+
+    fn setup_valid_game() -> Result<Game<InitialGame, SimplePlayer>, GameError> {
+        let mut game = setup_game(RuleSet::casual(), 1, 0)?;
+        game.set_host(&SimplePlayer::new("p1", 1))?;
+        game.add_spectator(SimplePlayer::new("s1", 10))?;
+        Ok(game)
+    }
+
+    #[test]
+    fn snapshot_roundtrip_initial() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        let snapshot = game.snapshot();
+        let json = snapshot.to_json()?;
+        let restored = GameSnapshot::<SimplePlayer>::from_json(&json)?;
+        assert_eq!(restored, snapshot);
+        let restored = match restored.restore(clock())? {
+            RestoredGame::Initial(g) => g,
+            RestoredGame::InProgress(_) => panic!("game should not have started"),
+        };
+        assert_eq!(restored.get_board(), game.get_board());
+        assert_eq!(
+            restored.get_team_one_spymaster(),
+            game.get_team_one_spymaster()
+        );
+        assert_eq!(
+            restored.get_team_two_spymaster(),
+            game.get_team_two_spymaster()
+        );
+        assert_eq!(restored.get_host_id(), Some(&1));
+        assert_eq!(restored.get_spectators(), game.get_spectators());
+        assert!(restored.can_begin());
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_roundtrip_in_progress() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?.begin()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let grey_move = (0..25)
+            .find(|x| game.get_board().is_grey_index(*x))
+            .unwrap();
+        let t1_move = (0..25)
+            .find(|x| game.get_board().is_team_one_index(*x))
+            .unwrap();
+        game.try_unravel(&p1, t1_move as u8)?;
+        game.try_unravel(&p1, grey_move as u8)?;
+
+        let json = game.snapshot().to_json()?;
+        let mut restored = match GameSnapshot::<SimplePlayer>::from_json(&json)?.restore(clock())? {
+            RestoredGame::InProgress(g) => g,
+            RestoredGame::Initial(_) => panic!("game should have started"),
+        };
+        assert_eq!(restored.get_board(), game.get_board());
+        assert_eq!(restored.get_team_one_score(), 7);
+        assert_eq!(restored.get_team_two_score(), 8);
+        assert_eq!(restored.get_next_turn(), Some(Team::TeamTwo));
+        assert_eq!(restored.spymaster_view(), game.spymaster_view());
+        // history survives, so the host can still undo.
+        assert_eq!(restored.undo_last_unravel(&p1)?, grey_move as u8);
+        assert_eq!(restored.get_next_turn(), Some(Team::TeamOne));
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_typestate_mismatch() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        let snapshot = game.snapshot();
+        assert!(Game::<InProgressGame, SimplePlayer>::restore(snapshot.clone(), clock()).is_err());
+        assert!(Game::<InitialGame, SimplePlayer>::restore(snapshot, clock()).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_rejects_bad_data() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        let mut snapshot = game.snapshot();
+        snapshot.version = SNAPSHOT_VERSION + 1;
+        let json = snapshot.to_json()?;
+        assert!(GameSnapshot::<SimplePlayer>::from_json(&json).is_err());
+
+        let mut snapshot = game.snapshot();
+        snapshot.team_one_spymaster = Some(2);
+        assert!(snapshot.restore(clock()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_rejects_inconsistent_games() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?.begin()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        let t1_move = (0..25)
+            .find(|x| game.get_board().is_team_one_index(*x))
+            .unwrap();
+        game.try_unravel(&p1, t1_move as u8)?;
+        let snapshot = game.snapshot();
+        let restore =
+            |s: GameSnapshot<SimplePlayer>| Game::<InProgressGame, _>::restore(s, clock()).err();
+
+        let mut value = serde_json::to_value(&snapshot).unwrap();
+        value["board"]["danger_index"] = serde_json::json!(30);
+        let bad: GameSnapshot<SimplePlayer> = serde_json::from_value(value).unwrap();
+        assert_eq!(restore(bad), Some(GameError::SnapshotInvalidBoard));
+
+        let mut bad = snapshot.clone();
+        bad.team_one_score += 1;
+        assert_eq!(restore(bad), Some(GameError::SnapshotScoreMismatch));
+
+        let mut bad = snapshot.clone();
+        bad.team_two_players.push(SimplePlayer::new("p1", 1));
+        assert_eq!(restore(bad), Some(GameError::SnapshotDuplicatePlayer(1)));
+        let mut bad = snapshot.clone();
+        bad.spectators.push(SimplePlayer::new("p2", 2));
+        assert_eq!(restore(bad), Some(GameError::SnapshotDuplicatePlayer(2)));
+
+        // an out of range record used to panic on undo.
+        let mut value = serde_json::to_value(&snapshot).unwrap();
+        value["unravel_history"][0]["tile_id"] = serde_json::json!(40);
+        let bad: GameSnapshot<SimplePlayer> = serde_json::from_value(value).unwrap();
+        assert_eq!(restore(bad), Some(GameError::SnapshotInvalidHistory));
+
+        // a running game with nobody to move used to panic on the next move.
+        let mut bad = snapshot.clone();
+        bad.next_turn = None;
+        assert_eq!(restore(bad), Some(GameError::SnapshotInvalidTurn));
+        let mut bad = setup_valid_game()?.snapshot();
+        bad.next_turn = Some(Team::TeamOne);
+        assert_eq!(
+            Game::<InitialGame, _>::restore(bad, clock()).err(),
+            Some(GameError::SnapshotInvalidTurn)
+        );
+        let mut bad = snapshot.clone();
+        bad.host_id = Some(10);
+        assert_eq!(restore(bad), Some(GameError::SnapshotHostMissing));
+        assert_eq!(restore(snapshot), None);
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_keeps_timers() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let clock = Arc::new(ManualClock::new());
+        game.set_clock(clock.clone());
+        game.set_timers(TimerConfig {
+            turn_limit: Some(Duration::from_secs(60)),
+            team_clock: Some(Duration::from_secs(100)),
+            ..TimerConfig::default()
+        });
+        let game = game.begin()?;
+        clock.advance(Duration::from_secs(30));
+        let json = game.snapshot().to_json()?;
+
+        let restored_clock = Arc::new(ManualClock::new());
+        let mut restored = Game::<InProgressGame, SimplePlayer>::restore(
+            GameSnapshot::from_json(&json)?,
+            restored_clock.clone(),
+        )?;
+        assert_eq!(restored.get_timers(), game.get_timers());
+        assert_eq!(
+            restored.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(70))
+        );
+        // the saved turn keeps running instead of starting over.
+        restored_clock.advance(Duration::from_secs(29));
+        restored.tick();
+        assert_eq!(restored.get_next_turn(), Some(Team::TeamOne));
+        restored_clock.advance(Duration::from_secs(1));
+        restored.tick();
+        assert_eq!(restored.get_next_turn(), Some(Team::TeamTwo));
+        assert_eq!(
+            restored.get_team_time_left(Team::TeamOne),
+            Some(Duration::from_secs(40))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn snapshot_keeps_round_history() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?.begin()?;
+        let p1 = SimplePlayer::new("p1", 1);
+        game.try_unravel(&p1, game.get_board().danger_index())?;
+        let options = RematchOptions {
+            rotate_spymasters: false,
+            losing_team_starts: true,
+        };
+        let game = game.rematch(&words(), options)?;
+        let restored = Game::<InitialGame, SimplePlayer>::restore(game.snapshot(), clock())?;
+        assert_eq!(restored.get_round_history(), game.get_round_history());
+        assert_eq!(restored.get_starting_team(), Team::TeamOne);
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use crate::board::TileKind;
+use crate::game::Team;
+use serde::{Deserialize, Serialize};
+
+/// Counters for a single player over one game.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct PlayerStats {
+    /// Tiles of the player's own team.
+    pub correct_reveals: u32,
+    /// Grey tiles.
+    pub bystanders_hit: u32,
+    /// Tiles handed to the other team.
+    pub opponent_tiles_revealed: u32,
+    pub assassins_hit: u32,
+    pub clues_given: u32,
+    /// Sum of the numbers of all clues given, for the average.
+    pub total_clue_number: u32,
+}
+
+impl PlayerStats {
+    /// Average number on the player's clues, `None` if they gave no clues.
+    pub fn average_clue_number(&self) -> Option<f64> {
+        if self.clues_given == 0 {
+            return None;
+        }
+        Some(self.total_clue_number as f64 / self.clues_given as f64)
+    }
+
+    pub fn total_reveals(&self) -> u32 {
+        self.correct_reveals
+            + self.bystanders_hit
+            + self.opponent_tiles_revealed
+            + self.assassins_hit
+    }
+
+    fn counter_for(&mut self, team: Team, kind: TileKind) -> &mut u32 {
+        match (team, kind) {
+            (_, TileKind::Grey) => &mut self.bystanders_hit,
+            (_, TileKind::Danger) => &mut self.assassins_hit,
+            (Team::TeamOne, TileKind::TeamOne) | (Team::TeamTwo, TileKind::TeamTwo) => {
+                &mut self.correct_reveals
+            }
+            _ => &mut self.opponent_tiles_revealed,
+        }
+    }
+
+    /// Count a tile revealed by this player while playing for `team`.
+    pub fn record_reveal(&mut self, team: Team, kind: TileKind) {
+        *self.counter_for(team, kind) += 1;
+    }
+
+    /// Take back a reveal counted with `record_reveal`.
+    pub fn revert_reveal(&mut self, team: Team, kind: TileKind) {
+        let counter = self.counter_for(team, kind);
+        *counter = counter.saturating_sub(1);
+    }
+
+    pub fn record_clue(&mut self, number: u8) {
+        self.clues_given += 1;
+        self.total_clue_number += number as u32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_reveals_by_kind() {
+        let mut stats = PlayerStats::default();
+        stats.record_reveal(Team::TeamOne, TileKind::TeamOne);
+        stats.record_reveal(Team::TeamOne, TileKind::TeamOne);
+        stats.record_reveal(Team::TeamOne, TileKind::TeamTwo);
+        stats.record_reveal(Team::TeamOne, TileKind::Grey);
+        stats.record_reveal(Team::TeamOne, TileKind::Danger);
+        assert_eq!(stats.correct_reveals, 2);
+        assert_eq!(stats.opponent_tiles_revealed, 1);
+        assert_eq!(stats.bystanders_hit, 1);
+        assert_eq!(stats.assassins_hit, 1);
+        assert_eq!(stats.total_reveals(), 5);
+
+        stats.revert_reveal(Team::TeamOne, TileKind::TeamOne);
+        assert_eq!(stats.correct_reveals, 1);
+        stats.revert_reveal(Team::TeamTwo, TileKind::TeamOne);
+        assert_eq!(stats.opponent_tiles_revealed, 0);
+        stats.revert_reveal(Team::TeamTwo, TileKind::TeamOne);
+        assert_eq!(stats.opponent_tiles_revealed, 0);
+    }
+
+    #[test]
+    fn stats_average_clue_number() {
+        let mut stats = PlayerStats::default();
+        assert_eq!(stats.average_clue_number(), None);
+        stats.record_clue(2);
+        stats.record_clue(3);
+        assert_eq!(stats.clues_given, 2);
+        assert_eq!(stats.average_clue_number(), Some(2.5));
+    }
+}
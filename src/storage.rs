@@ -0,0 +1,18 @@
+//! File helpers shared by the JSON-backed stores.
+
+use crate::errors::{ErrorSource, GameError};
+use std::fs;
+use std::path::Path;
+
+pub(crate) fn storage_error<E: std::error::Error + Send + Sync + 'static>(e: E) -> GameError {
+    GameError::Storage(ErrorSource::new(e))
+}
+
+/// Replace the file at `path` with `data`. The data goes to a file next to
+/// it first, so a crash never leaves half a file behind.
+pub(crate) fn write_atomically(path: &Path, data: &str) -> Result<(), GameError> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, data).map_err(storage_error)?;
+    fs::rename(&tmp, path).map_err(storage_error)?;
+    Ok(())
+}
@@ -0,0 +1,287 @@
+use crate::board::TileKind;
+use crate::game::{Clue, Game, GameState, GameStatus, Team, WinReason};
+use crate::players::{Avatar, Player, Presence};
+use crate::rules::Handicap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerSummary {
+    pub id: u32,
+    pub name: String,
+    pub avatar: Option<Avatar>,
+    pub pronouns: Option<String>,
+    pub presence: Presence,
+}
+
+impl PlayerSummary {
+    fn new<S, P: Player>(game: &Game<S, P>, player: &P) -> Self {
+        PlayerSummary {
+            id: *player.get_id(),
+            name: String::from(player.get_name()),
+            avatar: player.get_avatar().cloned(),
+            pronouns: player.get_pronouns().map(String::from),
+            presence: game.get_presence(player),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TeamCatalog {
+    pub players: Vec<PlayerSummary>,
+    pub spymaster: Option<PlayerSummary>,
+    pub score: u8,
+}
+
+/// A tile as guessers see it. `kind` is only filled in once the tile is unravelled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlainTile {
+    pub word: String,
+    pub kind: Option<TileKind>,
+}
+
+/// A tile as spymasters see it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FullTile {
+    pub word: String,
+    pub kind: TileKind,
+    pub unraveled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub tiles: Vec<PlainTile>,
+    pub team_one: TeamCatalog,
+    pub team_two: TeamCatalog,
+    pub spectators: Vec<PlayerSummary>,
+    pub next_turn: Option<Team>,
+    pub clue: Option<Clue>,
+    pub guesses_left: Option<u8>,
+    pub status: GameStatus,
+    pub winner: Option<(Team, WinReason)>,
+    pub handicap: Option<Handicap>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpymasterView {
+    pub tiles: Vec<FullTile>,
+    pub team_one: TeamCatalog,
+    pub team_two: TeamCatalog,
+    pub spectators: Vec<PlayerSummary>,
+    pub next_turn: Option<Team>,
+    pub clue: Option<Clue>,
+    pub guesses_left: Option<u8>,
+    pub status: GameStatus,
+    pub winner: Option<(Team, WinReason)>,
+    pub handicap: Option<Handicap>,
+}
+
+/// What spectators get to see. Only `Full` once the game is over and the
+/// lobby lets spectators see the key card.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SpectatorView {
+    Plain(PlayerView),
+    Full(SpymasterView),
+}
+
+fn spectator_summaries<S, P: Player>(game: &Game<S, P>) -> Vec<PlayerSummary> {
+    game.get_spectators()
+        .into_iter()
+        .map(|p| PlayerSummary::new(game, p))
+        .collect()
+}
+
+fn team_catalogs<S, P: Player>(game: &Game<S, P>) -> (TeamCatalog, TeamCatalog) {
+    let team_one = TeamCatalog {
+        players: game
+            .get_team_one_players()
+            .into_iter()
+            .map(|p| PlayerSummary::new(game, p))
+            .collect(),
+        spymaster: game
+            .get_team_one_spymaster()
+            .map(|p| PlayerSummary::new(game, p)),
+        score: game.get_team_one_score(),
+    };
+    let team_two = TeamCatalog {
+        players: game
+            .get_team_two_players()
+            .into_iter()
+            .map(|p| PlayerSummary::new(game, p))
+            .collect(),
+        spymaster: game
+            .get_team_two_spymaster()
+            .map(|p| PlayerSummary::new(game, p)),
+        score: game.get_team_two_score(),
+    };
+    (team_one, team_two)
+}
+
+impl PlayerView {
+    pub fn new<S: GameState, P: Player>(game: &Game<S, P>) -> Self {
+        let board = game.get_board();
+        let tiles = board
+            .words()
+            .iter()
+            .enumerate()
+            .map(|(i, word)| PlainTile {
+                word: word.clone(),
+                kind: if board.is_word_unravelled(i) {
+                    Some(board.tile_kind(i))
+                } else {
+                    None
+                },
+            })
+            .collect();
+        let (team_one, team_two) = team_catalogs(game);
+        PlayerView {
+            tiles,
+            team_one,
+            team_two,
+            spectators: spectator_summaries(game),
+            next_turn: game.get_next_turn(),
+            clue: game.get_current_clue().cloned(),
+            guesses_left: game.get_guesses_left(),
+            status: game.status(),
+            winner: game.get_winner(),
+            handicap: game.get_rules().handicap,
+        }
+    }
+}
+
+impl SpymasterView {
+    pub fn new<S: GameState, P: Player>(game: &Game<S, P>) -> Self {
+        let board = game.get_board();
+        let tiles = board
+            .words()
+            .iter()
+            .enumerate()
+            .map(|(i, word)| FullTile {
+                word: word.clone(),
+                kind: board.tile_kind(i),
+                unraveled: board.is_word_unravelled(i),
+            })
+            .collect();
+        let (team_one, team_two) = team_catalogs(game);
+        SpymasterView {
+            tiles,
+            team_one,
+            team_two,
+            spectators: spectator_summaries(game),
+            next_turn: game.get_next_turn(),
+            clue: game.get_current_clue().cloned(),
+            guesses_left: game.get_guesses_left(),
+            status: game.status(),
+            winner: game.get_winner(),
+            handicap: game.get_rules().handicap,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::GameError;
+    use crate::game::test_support::{setup_game, words};
+    use crate::game::InitialGame;
+    use crate::observer::{EventLog, GameEvent};
+    use crate::players::{DisplayName, PlayerProfile, SimplePlayer};
+    use crate::rules::RuleSet;
+
+    fn setup_valid_game() -> Result<Game<InitialGame, SimplePlayer>, GameError> {
+        setup_game(RuleSet::casual(), 0, 1)
+    }
+
+    #[test]
+    fn player_view_hides_key_card() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?.begin()?;
+        let p3 = SimplePlayer::new("p3", 3);
+        let grey_move = (0..25)
+            .find(|x| game.get_board().is_grey_index(*x))
+            .unwrap();
+        game.try_unravel(&p3, grey_move as u8)?;
+
+        let view = game.player_view();
+        assert_eq!(view.tiles.len(), 25);
+        for (i, tile) in view.tiles.iter().enumerate() {
+            if i == grey_move {
+                assert_eq!(tile.kind, Some(TileKind::Grey));
+            } else {
+                assert_eq!(tile.kind, None, "tile {} leaked its kind", i);
+            }
+        }
+        assert_eq!(view.next_turn, Some(Team::TeamTwo));
+        assert_eq!(view.status, GameStatus::InProgress);
+        // serialized form must not carry the key card either.
+        let json = serde_json::to_string(&view).unwrap();
+        assert!(!json.contains("Danger"));
+        Ok(())
+    }
+
+    #[test]
+    fn spymaster_view_shows_key_card() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        let view = game.spymaster_view();
+        let board = game.get_board();
+        for (i, tile) in view.tiles.iter().enumerate() {
+            assert_eq!(tile.kind, board.tile_kind(i));
+            assert!(!tile.unraveled);
+            assert_eq!(tile.word, board.words()[i]);
+        }
+        assert_eq!(view.status, GameStatus::Waiting);
+        assert_eq!(view.next_turn, None);
+        Ok(())
+    }
+
+    #[test]
+    fn view_team_catalogs() -> Result<(), GameError> {
+        let game = setup_valid_game()?;
+        let view = game.player_view();
+        let ids: Vec<u32> = view.team_one.players.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![1, 3]);
+        let ids: Vec<u32> = view.team_two.players.iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![2, 4]);
+        assert_eq!(view.team_one.spymaster.as_ref().unwrap().id, 1);
+        assert_eq!(view.team_two.spymaster.as_ref().unwrap().name, "p4");
+        assert_eq!(view.team_one.score, 8);
+        assert_eq!(view.team_two.score, 8);
+        Ok(())
+    }
+
+    #[test]
+    fn views_show_player_metadata() -> Result<(), GameError> {
+        let mut game: Game<InitialGame, PlayerProfile> = Game::new(&words(), RuleSet::casual())?;
+        let mut ada = PlayerProfile::new(DisplayName::new("Ada")?, 1);
+        ada.set_pronouns(Some("she/her"));
+        ada.set_avatar(Some(Avatar::Color(String::from("#336699"))))?;
+        ada.set_presence(Presence::Away);
+        game.add_player_team_one(ada);
+        let view = game.player_view();
+        let summary = &view.team_one.players[0];
+        assert_eq!(summary.name, "Ada");
+        assert_eq!(summary.pronouns.as_deref(), Some("she/her"));
+        assert_eq!(summary.avatar, Some(Avatar::Color(String::from("#336699"))));
+        assert_eq!(summary.presence, Presence::Away);
+        Ok(())
+    }
+
+    #[test]
+    fn views_show_who_is_connected() -> Result<(), GameError> {
+        let mut game = setup_valid_game()?;
+        let log = EventLog::new();
+        game.add_observer(Box::new(log.clone()));
+        game.set_presence(3, Presence::Away);
+        game.set_presence(3, Presence::Away);
+        let view = game.player_view();
+        let presence: Vec<Presence> = view.team_one.players.iter().map(|p| p.presence).collect();
+        assert_eq!(presence, vec![Presence::Online, Presence::Away]);
+        // only real changes are announced.
+        assert_eq!(
+            log.events(),
+            vec![GameEvent::PresenceChanged {
+                player_id: 3,
+                presence: Presence::Away
+            }]
+        );
+        Ok(())
+    }
+}
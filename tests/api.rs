@@ -0,0 +1,492 @@
+use codeword::accounts::{hash_password, AccountStore};
+use codeword::board::TileKind;
+use codeword::clock::ManualClock;
+use codeword::errors::{GameError, Problem};
+use codeword::game::{GameStatus, Team, WinReason};
+use codeword::i18n::{Locale, Localized};
+use codeword::players::{Avatar, DisplayName, Player, Presence};
+use codeword::registry::PlayerRegistry;
+use codeword::server::{self, ServerState};
+use codeword::views::{PlayerView, SpymasterView};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+use warp::http::StatusCode;
+use warp::test::request;
+use warp::Filter;
+
+fn api() -> impl Filter<Extract = impl warp::Reply, Error = Infallible> + Clone + 'static {
+    server::api(ServerState::default().shared())
+}
+
+fn timed_api(
+    clock: Arc<ManualClock>,
+) -> impl Filter<Extract = impl warp::Reply, Error = Infallible> + Clone + 'static {
+    let state = ServerState::with_clock(
+        PlayerRegistry::in_memory(),
+        AccountStore::in_memory(),
+        clock,
+    );
+    server::api(state.shared())
+}
+
+struct Reply {
+    status: StatusCode,
+    body: Vec<u8>,
+}
+
+impl Reply {
+    fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_slice(&self.body).expect("json body")
+    }
+
+    fn problem(&self) -> String {
+        self.json::<Problem>().code
+    }
+}
+
+async fn send<F>(api: &F, method: &str, path: &str, token: Option<&str>, body: Value) -> Reply
+where
+    F: Filter + 'static,
+    F::Extract: warp::Reply + Send,
+{
+    let mut req = request().method(method).path(path);
+    if let Some(token) = token {
+        req = req.header("authorization", format!("Bearer {}", token));
+    }
+    if !body.is_null() {
+        req = req.json(&body);
+    }
+    let res = req.reply(api).await;
+    Reply {
+        status: res.status(),
+        body: res.body().to_vec(),
+    }
+}
+
+/// A player in a lobby.
+struct Seat {
+    id: u32,
+    token: String,
+}
+
+async fn create_lobby<F>(api: &F) -> String
+where
+    F: Filter + 'static,
+    F::Extract: warp::Reply + Send,
+{
+    let res = send(api, "POST", "/lobby", None, Value::Null).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    res.json()
+}
+
+async fn join<F>(api: &F, lobby: &str, name: &str) -> Seat
+where
+    F: Filter + 'static,
+    F::Extract: warp::Reply + Send,
+{
+    let path = format!("/lobby/{}/players", lobby);
+    let res = send(api, "POST", &path, None, json!({ "name": name })).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    let joined: Value = res.json();
+    Seat {
+        id: joined["player_id"].as_u64().unwrap() as u32,
+        token: String::from(joined["token"].as_str().unwrap()),
+    }
+}
+
+/// A lobby with two teams of two, the first of each the spymaster, all
+/// ready. Returns the lobby id and seats in team order.
+async fn started_lobby<F>(api: &F) -> (String, Vec<Seat>)
+where
+    F: Filter + 'static,
+    F::Extract: warp::Reply + Send,
+{
+    let lobby = create_lobby(api).await;
+    let mut seats = Vec::new();
+    for name in ["Ann", "Bob", "Cat", "Dan"].iter() {
+        seats.push(join(api, &lobby, name).await);
+    }
+    for (seat, team) in seats
+        .iter()
+        .zip(["TeamOne", "TeamOne", "TeamTwo", "TeamTwo"].iter())
+    {
+        let path = format!("/lobby/{}/joinTeam", lobby);
+        let res = send(
+            api,
+            "POST",
+            &path,
+            Some(&seat.token),
+            json!({ "team": team }),
+        )
+        .await;
+        assert_eq!(res.status, StatusCode::NO_CONTENT);
+    }
+    for seat in [&seats[0], &seats[2]].iter() {
+        let path = format!("/lobby/{}/becomeSpymaster", lobby);
+        let res = send(api, "POST", &path, Some(&seat.token), Value::Null).await;
+        assert_eq!(res.status, StatusCode::NO_CONTENT);
+    }
+    for (i, seat) in seats.iter().enumerate() {
+        let path = format!("/lobby/{}/ready", lobby);
+        let res = send(api, "PUT", &path, Some(&seat.token), Value::Null).await;
+        assert_eq!(res.status, StatusCode::OK);
+        let ready: Value = res.json();
+        assert_eq!(ready["started"], json!(i == 3));
+    }
+    (lobby, seats)
+}
+
+#[tokio::test]
+async fn api_plays_a_full_game() -> Result<(), GameError> {
+    let api = api();
+    let (lobby, seats) = started_lobby(&api).await;
+
+    let path = format!("/lobby/{}/gameviews/player", lobby);
+    let view: PlayerView = send(&api, "GET", &path, Some(&seats[1].token), Value::Null)
+        .await
+        .json();
+    assert_eq!(view.status, GameStatus::InProgress);
+    assert_eq!(
+        view.team_one.spymaster.as_ref().map(|p| p.id),
+        Some(seats[0].id)
+    );
+    let team = view.next_turn.unwrap();
+    let other = match team {
+        Team::TeamOne => Team::TeamTwo,
+        Team::TeamTwo => Team::TeamOne,
+    };
+    let (spymaster, guesser, kind) = match team {
+        Team::TeamOne => (&seats[0], &seats[1], TileKind::TeamOne),
+        Team::TeamTwo => (&seats[2], &seats[3], TileKind::TeamTwo),
+    };
+    let (other_spymaster, other_guesser) = match team {
+        Team::TeamOne => (&seats[2], &seats[3]),
+        Team::TeamTwo => (&seats[0], &seats[1]),
+    };
+
+    // spectators see what guessers see while the game runs.
+    let eve = join(&api, &lobby, "Eve").await;
+    let seen: PlayerView = send(&api, "GET", &path, Some(&eve.token), Value::Null)
+        .await
+        .json();
+    assert!(seen.tiles.iter().all(|t| t.kind.is_none()));
+
+    let path = format!("/lobby/{}/gameviews/spymaster", lobby);
+    let key: SpymasterView = send(&api, "GET", &path, Some(&spymaster.token), Value::Null)
+        .await
+        .json();
+    let targets: Vec<u8> = key
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, t)| t.kind == kind)
+        .map(|(i, _)| i as u8)
+        .collect();
+
+    // guessing before the clue is refused.
+    let path = format!("/lobby/{}/unravel/", lobby);
+    let res = send(
+        &api,
+        "POST",
+        &path,
+        Some(&guesser.token),
+        json!({ "tile": targets[0] }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+
+    let path = format!("/lobby/{}/clue", lobby);
+    let clue = json!({ "word": "first", "number": 1 });
+    let res = send(&api, "POST", &path, Some(&spymaster.token), clue).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+
+    // the spymaster knows the answers, so only guessers reveal.
+    let path = format!("/lobby/{}/unravel/", lobby);
+    let res = send(
+        &api,
+        "POST",
+        &path,
+        Some(&spymaster.token),
+        json!({ "tile": targets[0] }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    assert_eq!(res.problem(), "spymaster_cannot_guess");
+
+    let res = send(
+        &api,
+        "POST",
+        &path,
+        Some(&guesser.token),
+        json!({ "tile": targets[0] }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json::<PlayerView>().next_turn, Some(team));
+
+    // stop while ahead.
+    let path = format!("/lobby/{}/endTurn", lobby);
+    let res = send(&api, "POST", &path, Some(&guesser.token), Value::Null).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    let path = format!("/lobby/{}/gameviews/player", lobby);
+    let view: PlayerView = send(&api, "GET", &path, Some(&guesser.token), Value::Null)
+        .await
+        .json();
+    assert_eq!(view.next_turn, Some(other));
+
+    // the other team opens one of ours, which hands the turn back.
+    let path = format!("/lobby/{}/clue", lobby);
+    let clue = json!({ "word": "second", "number": 1 });
+    let res = send(&api, "POST", &path, Some(&other_spymaster.token), clue).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    let path = format!("/lobby/{}/unravel/", lobby);
+    let res = send(
+        &api,
+        "POST",
+        &path,
+        Some(&other_guesser.token),
+        json!({ "tile": targets[1] }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.json::<PlayerView>().next_turn, Some(team));
+
+    let path = format!("/lobby/{}/clue", lobby);
+    let clue = json!({ "word": "everything", "number": targets.len() - 2 });
+    let res = send(&api, "POST", &path, Some(&spymaster.token), clue).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+
+    let path = format!("/lobby/{}/unravel/", lobby);
+    let mut view = None;
+    for tile in targets[2..].iter() {
+        let res = send(
+            &api,
+            "POST",
+            &path,
+            Some(&guesser.token),
+            json!({ "tile": tile }),
+        )
+        .await;
+        assert_eq!(res.status, StatusCode::OK);
+        view = Some(res.json::<PlayerView>());
+    }
+    let view = view.unwrap();
+    assert_eq!(view.status, GameStatus::Over);
+    assert_eq!(view.winner, Some((team, WinReason::ScoreReached)));
+
+    let res = send(
+        &api,
+        "POST",
+        &path,
+        Some(&guesser.token),
+        json!({ "tile": 0 }),
+    )
+    .await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+
+    let path = format!("/lobby/{}/actionLogs/", lobby);
+    let logs: Vec<Value> = send(&api, "GET", &path, Some(&seats[0].token), Value::Null)
+        .await
+        .json();
+    let unraveled = logs
+        .iter()
+        .filter(|e| e["event"].get("TileUnraveled").is_some())
+        .count();
+    assert_eq!(unraveled, targets.len());
+    // the opening turn, the end of it and the wrong reveal.
+    let turns = logs
+        .iter()
+        .filter(|e| e["event"].get("TurnChanged").is_some())
+        .count();
+    assert_eq!(turns, 3);
+    assert!(logs
+        .iter()
+        .any(|e| e["message"].as_str().unwrap().contains("everything")));
+    assert!(logs.last().unwrap()["event"].get("GameWon").is_some());
+
+    // the result went into both teams' ratings.
+    let board: Vec<Value> = send(
+        &api,
+        "GET",
+        "/leaderboard?role=Spymaster",
+        None,
+        Value::Null,
+    )
+    .await
+    .json();
+    assert_eq!(board.len(), 2);
+    assert_eq!(board[0]["player_id"], json!(spymaster.id));
+
+    // spectators get the key card once the game is over.
+    let fay = join(&api, &lobby, "Fay").await;
+    let path = format!("/lobby/{}/gameviews/player", lobby);
+    let seen: SpymasterView = send(&api, "GET", &path, Some(&fay.token), Value::Null)
+        .await
+        .json();
+    let kinds = |view: &SpymasterView| view.tiles.iter().map(|t| t.kind).collect::<Vec<_>>();
+    assert_eq!(kinds(&seen), kinds(&key));
+    Ok(())
+}
+
+#[tokio::test]
+async fn api_rejects_players_out_of_place() -> Result<(), GameError> {
+    let api = api();
+    let lobby = create_lobby(&api).await;
+    let ann = join(&api, &lobby, "Ann").await;
+
+    let path = format!("/lobby/{}/gameviews/player", lobby);
+    let res = send(&api, "GET", &path, None, Value::Null).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(res.problem(), "invalid_session");
+
+    // errors come in the language asked for.
+    let res = request()
+        .method("GET")
+        .path(&path)
+        .header("accept-language", "de-DE, en;q=0.5")
+        .reply(&api)
+        .await;
+    let problem: Problem = serde_json::from_slice(res.body()).unwrap();
+    assert_eq!(
+        problem.detail,
+        GameError::InvalidSession.localize(Locale::De)
+    );
+
+    let path = format!("/lobby/{}/ready", lobby);
+    let res = send(&api, "PUT", &path, Some(&ann.token), Value::Null).await;
+    assert_eq!(res.problem(), "spectator_cannot_act");
+
+    let path = format!("/lobby/{}/unravel/", lobby);
+    let res = send(&api, "POST", &path, Some(&ann.token), json!({ "tile": 0 })).await;
+    assert_eq!(res.problem(), "game_not_started");
+
+    let (lobby, seats) = started_lobby(&api).await;
+    let path = format!("/lobby/{}/gameviews/spymaster", lobby);
+    let res = send(&api, "GET", &path, Some(&seats[1].token), Value::Null).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+    assert_eq!(res.problem(), "not_spymaster");
+
+    // nothing to decline while the spymaster is around.
+    let path = format!("/lobby/{}/declineSpymaster", lobby);
+    let res = send(&api, "POST", &path, Some(&seats[1].token), Value::Null).await;
+    assert_eq!(res.problem(), "no_spymaster_offer");
+
+    // tokens only work in their own lobby.
+    let path = format!("/lobby/{}/gameviews/spymaster", lobby);
+    let res = send(&api, "GET", &path, Some(&ann.token), Value::Null).await;
+    assert_eq!(res.status, StatusCode::FORBIDDEN);
+
+    let late = join(&api, &lobby, "Eve").await;
+    let path = format!("/lobby/{}/joinTeam", lobby);
+    let res = send(
+        &api,
+        "POST",
+        &path,
+        Some(&late.token),
+        json!({ "team": "TeamOne" }),
+    )
+    .await;
+    assert_eq!(res.problem(), "game_already_started");
+    Ok(())
+}
+
+#[tokio::test]
+async fn api_accounts_keep_their_id_in_lobbies() -> Result<(), GameError> {
+    let api = api();
+    let account = json!({ "username": "ann", "password": "correct horse", "name": "Ann" });
+    let res = send(&api, "POST", "/accounts/signup", None, account).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    let signed_up: Value = res.json();
+    let token = signed_up["token"].as_str().unwrap();
+
+    let lobby = create_lobby(&api).await;
+    let path = format!("/lobby/{}/players", lobby);
+    let res = send(&api, "POST", &path, Some(token), json!({})).await;
+    let joined: Value = res.json();
+    assert_eq!(joined["player_id"], signed_up["player_id"]);
+
+    let path = format!("/lobby/{}/gameviews/player", lobby);
+    let view: PlayerView = send(&api, "GET", &path, joined["token"].as_str(), Value::Null)
+        .await
+        .json();
+    assert_eq!(view.spectators[0].name, "Ann");
+    Ok(())
+}
+
+#[tokio::test]
+async fn api_lobbies_show_registry_profiles() -> Result<(), GameError> {
+    let mut registry = PlayerRegistry::in_memory();
+    let ann = *registry.register(DisplayName::new("Ann")?)?.get_id();
+    registry.update(ann, |profile| {
+        profile.set_pronouns(Some("she/her"));
+        profile.set_avatar(Some(Avatar::Color(String::from("#aa3366"))))
+    })?;
+    let mut accounts = AccountStore::in_memory();
+    accounts.upgrade_guest(&registry, ann, "ann", hash_password("correct horse")?)?;
+    let api = server::api(ServerState::new(registry, accounts).shared());
+
+    let login = json!({ "username": "ann", "password": "correct horse" });
+    let res = send(&api, "POST", "/accounts/login", None, login).await;
+    assert_eq!(res.status, StatusCode::OK);
+    let logged_in: Value = res.json();
+    let lobby = create_lobby(&api).await;
+    let path = format!("/lobby/{}/players", lobby);
+    let res = send(&api, "POST", &path, logged_in["token"].as_str(), json!({})).await;
+    let joined: Value = res.json();
+
+    let path = format!("/lobby/{}/gameviews/player", lobby);
+    let view: PlayerView = send(&api, "GET", &path, joined["token"].as_str(), Value::Null)
+        .await
+        .json();
+    let summary = &view.spectators[0];
+    assert_eq!(summary.pronouns.as_deref(), Some("she/her"));
+    assert_eq!(summary.avatar, Some(Avatar::Color(String::from("#aa3366"))));
+    assert_eq!(summary.presence, Presence::Online);
+    Ok(())
+}
+
+#[tokio::test]
+async fn api_offers_the_role_of_an_idle_spymaster() -> Result<(), GameError> {
+    let clock = Arc::new(ManualClock::new());
+    let api = timed_api(clock.clone());
+    let (declined, declined_seats) = started_lobby(&api).await;
+    let (accepted, accepted_seats) = started_lobby(&api).await;
+    clock.advance(Duration::from_secs(121));
+
+    // the guesser of the team to move gets the offer.
+    let mut guessers = Vec::new();
+    for (lobby, seats) in [(&declined, &declined_seats), (&accepted, &accepted_seats)].iter() {
+        let path = format!("/lobby/{}/gameviews/player", lobby);
+        let view: PlayerView = send(&api, "GET", &path, Some(&seats[0].token), Value::Null)
+            .await
+            .json();
+        let guesser = match view.next_turn.unwrap() {
+            Team::TeamOne => &seats[1],
+            Team::TeamTwo => &seats[3],
+        };
+        let path = format!("/lobby/{}/actionLogs/", lobby);
+        let logs: Vec<Value> = send(&api, "GET", &path, Some(&guesser.token), Value::Null)
+            .await
+            .json();
+        let offer = &logs.last().unwrap()["event"]["SpymasterOffered"];
+        assert_eq!(offer["offered_to"], json!(guesser.id));
+        guessers.push(guesser);
+    }
+
+    let path = format!("/lobby/{}/declineSpymaster", declined);
+    let res = send(&api, "POST", &path, Some(&guessers[0].token), Value::Null).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    let res = send(&api, "POST", &path, Some(&guessers[0].token), Value::Null).await;
+    assert_eq!(res.problem(), "no_spymaster_offer");
+
+    let path = format!("/lobby/{}/becomeSpymaster", accepted);
+    let res = send(&api, "POST", &path, Some(&guessers[1].token), Value::Null).await;
+    assert_eq!(res.status, StatusCode::NO_CONTENT);
+    let path = format!("/lobby/{}/gameviews/spymaster", accepted);
+    let res = send(&api, "GET", &path, Some(&guessers[1].token), Value::Null).await;
+    assert_eq!(res.status, StatusCode::OK);
+    Ok(())
+}